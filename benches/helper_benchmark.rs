@@ -2,7 +2,9 @@
 extern crate criterion;
 use criterion::Criterion;
 
-use ckb_merkle_mountain_range::{leaf_index_to_mmr_size, leaf_index_to_pos};
+use ckb_merkle_mountain_range::{
+    leaf_index_to_mmr_size, leaf_index_to_pos, mmr_size_to_leaf_count,
+};
 
 use rand::{thread_rng, Rng};
 
@@ -22,6 +24,14 @@ fn bench(c: &mut Criterion) {
             leaf_index_to_mmr_size(leaf_index);
         });
     });
+
+    c.bench_function("mmr_size_to_leaf_count", |b| {
+        let mut rng = thread_rng();
+        b.iter(|| {
+            let leaf_index = rng.gen_range(50_000_000_000, 70_000_000_000);
+            mmr_size_to_leaf_count(leaf_index_to_mmr_size(leaf_index)).unwrap();
+        });
+    });
 }
 
 criterion_group!(benches, bench);
@@ -1,7 +1,7 @@
 #[macro_use]
 extern crate criterion;
 
-use criterion::Criterion;
+use criterion::{BenchmarkId, Criterion};
 
 use bytes::Bytes;
 use ckb_merkle_mountain_range::{util::MemStore, Error, MMRStore, Merge, Result, MMR};
@@ -52,14 +52,37 @@ fn prepare_mmr(count: u32) -> (u64, MemStore<NumberHash>, Vec<u64>) {
     (mmr_size, store, positions)
 }
 
+fn prepare_mmr_batch(count: u32) -> (u64, MemStore<NumberHash>, Vec<u64>) {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    let positions = mmr
+        .push_batch((0u32..count).map(|i| NumberHash::try_from(i).unwrap()))
+        .unwrap();
+    let mmr_size = mmr.mmr_size();
+    mmr.commit().expect("write to store");
+    (mmr_size, store, positions)
+}
+
 fn bench(c: &mut Criterion) {
-    c.bench_function_over_inputs(
-        "MMR insert",
-        |b, &&size| {
-            b.iter(|| prepare_mmr(size));
-        },
-        &[10_000, 100_000, 100_0000],
-    );
+    {
+        let mut group = c.benchmark_group("MMR insert");
+        for size in [10_000, 100_000, 100_0000].iter() {
+            group.bench_with_input(BenchmarkId::from_parameter(size), size, |b, &size| {
+                b.iter(|| prepare_mmr(size));
+            });
+        }
+        group.finish();
+    }
+
+    {
+        let mut group = c.benchmark_group("MMR insert batch");
+        for size in [10_000, 100_000, 100_0000].iter() {
+            group.bench_with_input(BenchmarkId::from_parameter(size), size, |b, &size| {
+                b.iter(|| prepare_mmr_batch(size));
+            });
+        }
+        group.finish();
+    }
 
     c.bench_function("MMR gen proof", |b| {
         let (mmr_size, store, positions) = prepare_mmr(100_0000);
@@ -0,0 +1,76 @@
+//! Ancestry proof
+//!
+//! Prove that a previous MMR is a prefix of the current MMR, like the consistency proof of
+//! Certificate Transparency. Every peak of the previous MMR is a node of the current MMR,
+//! so the proof is the previous peaks and a merkle proof of them in the current MMR.
+//!
+//! references:
+//! https://tools.ietf.org/html/rfc6962#section-2.1.2
+
+use crate::helper::{get_peaks, is_valid_mmr_size};
+use crate::vec::Vec;
+use crate::{Error, MerkleProof, NodeMerge, PeakBagging, Result, RightToLeftBagging};
+use core::fmt::Debug;
+
+#[derive(Debug)]
+pub struct AncestryProof<T, M, B = RightToLeftBagging> {
+    prev_mmr_size: u64,
+    prev_peaks: Vec<T>,
+    prev_peaks_proof: MerkleProof<T, M, B>,
+}
+
+impl<T: PartialEq + Debug + Clone, M: NodeMerge<Item = T>, B: PeakBagging<T>>
+    AncestryProof<T, M, B>
+{
+    pub fn new(
+        prev_mmr_size: u64,
+        prev_peaks: Vec<T>,
+        prev_peaks_proof: MerkleProof<T, M, B>,
+    ) -> Self {
+        AncestryProof {
+            prev_mmr_size,
+            prev_peaks,
+            prev_peaks_proof,
+        }
+    }
+
+    pub fn prev_mmr_size(&self) -> u64 {
+        self.prev_mmr_size
+    }
+
+    pub fn prev_peaks(&self) -> &[T] {
+        &self.prev_peaks
+    }
+
+    pub fn prev_peaks_proof(&self) -> &MerkleProof<T, M, B> {
+        &self.prev_peaks_proof
+    }
+
+    /// verify that the MMR of `prev_root` is a prefix of the MMR of `root`
+    /// 1. bagging previous peaks and compare with `prev_root`
+    /// 2. verify previous peaks are nodes of the current MMR
+    pub fn verify_ancestor(&self, root: B::Root, prev_root: B::Root) -> Result<bool>
+    where
+        B::Root: PartialEq,
+    {
+        if self.prev_mmr_size == 0
+            || self.prev_mmr_size > self.prev_peaks_proof.mmr_size()
+            || !is_valid_mmr_size(self.prev_mmr_size)
+        {
+            return Err(Error::InvalidMMRSize);
+        }
+        let peaks_pos = get_peaks(self.prev_mmr_size);
+        if peaks_pos.len() != self.prev_peaks.len() {
+            return Err(Error::CorruptedProof);
+        }
+        let calculated_prev_root = B::bag_peaks::<M>(self.prev_peaks.clone(), self.prev_mmr_size)?;
+        if calculated_prev_root != prev_root {
+            return Ok(false);
+        }
+        let nodes = peaks_pos
+            .into_iter()
+            .zip(self.prev_peaks.iter().cloned())
+            .collect();
+        self.prev_peaks_proof.verify_nodes(root, nodes)
+    }
+}
@@ -0,0 +1,139 @@
+//! Async Merkle Mountain Range
+//!
+//! `AsyncMMR` works like `MMR` on top of an `AsyncMMRStore`, so stores backed by
+//! async databases or remote services don't block executor threads.
+//!
+//! `AsyncMMR` only pushes, gets root and generates proofs. It has no prune list and
+//! can't rewind, so `AsyncMMRStore` has no `truncate` or `remove`, use `MMR` for them.
+
+use crate::helper::{get_peaks, parent_offset, pos_height_in_tree};
+use crate::mmr::gen_proof_positions;
+use crate::vec::Vec;
+use crate::{Error, MerkleProof, NodeMerge, PeakBagging, Result, RightToLeftBagging};
+use core::fmt::Debug;
+use core::future::Future;
+use core::marker::PhantomData;
+
+/// Futures of the store are `Send`, so futures of `AsyncMMR` can be spawned on
+/// multi-threaded executors as long as the store itself is `Send` and `Sync`
+pub trait AsyncMMRStore<Elem> {
+    fn get_elem(&self, pos: u64) -> impl Future<Output = Result<Option<Elem>>> + Send;
+    fn append(&mut self, pos: u64, elems: Vec<Elem>) -> impl Future<Output = Result<()>> + Send;
+}
+
+pub struct AsyncMMR<T, M, S: AsyncMMRStore<T>, B = RightToLeftBagging> {
+    mmr_size: u64,
+    // pending elems, appended to store on commit
+    memory_batch: Vec<(u64, Vec<T>)>,
+    store: S,
+    merge: PhantomData<M>,
+    bagging: PhantomData<B>,
+}
+
+impl<
+        T: Clone + PartialEq + Debug,
+        M: NodeMerge<Item = T>,
+        S: AsyncMMRStore<T>,
+        B: PeakBagging<T>,
+    > AsyncMMR<T, M, S, B>
+{
+    pub fn new(mmr_size: u64, store: S) -> Self {
+        AsyncMMR {
+            mmr_size,
+            memory_batch: Vec::new(),
+            store,
+            merge: PhantomData,
+            bagging: PhantomData,
+        }
+    }
+
+    pub fn mmr_size(&self) -> u64 {
+        self.mmr_size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mmr_size == 0
+    }
+
+    async fn get_elem(&self, pos: u64) -> Result<Option<T>> {
+        for (start_pos, elems) in self.memory_batch.iter().rev() {
+            if pos < *start_pos {
+                continue;
+            } else if pos < start_pos + elems.len() as u64 {
+                return Ok(elems.get((pos - start_pos) as usize).cloned());
+            } else {
+                break;
+            }
+        }
+        self.store.get_elem(pos).await
+    }
+
+    async fn get_stored_elem(&self, pos: u64) -> Result<T> {
+        self.get_elem(pos).await?.ok_or(Error::InconsistentStore)
+    }
+
+    // push a element and return position, the element is hashed by `M::hash_leaf` before stored
+    pub async fn push(&mut self, elem: T) -> Result<u64> {
+        let mut elems: Vec<T> = Vec::new();
+        // position of new elem
+        let elem_pos = self.mmr_size;
+        elems.push(M::hash_leaf(elem)?);
+        let mut height = 0u32;
+        let mut pos = elem_pos;
+        // continue to merge tree node if next pos heigher than current
+        while pos_height_in_tree(pos + 1) > height {
+            pos += 1;
+            let left_pos = pos - parent_offset(height);
+            // left child is a previous peak, right child is the last new elem
+            let left_elem = self.get_stored_elem(left_pos).await?;
+            let right_elem = elems.last().expect("last elem");
+            let parent_elem = M::merge_node(pos, height + 1, &left_elem, right_elem)?;
+            elems.push(parent_elem);
+            height += 1
+        }
+        // store hashes
+        self.memory_batch.push((elem_pos, elems));
+        // update mmr_size
+        self.mmr_size = pos + 1;
+        Ok(elem_pos)
+    }
+
+    /// get_root
+    pub async fn get_root(&self) -> Result<B::Root> {
+        if self.mmr_size == 0 {
+            return Err(Error::GetRootOnEmpty);
+        }
+        let mut peaks: Vec<T> = Vec::new();
+        for peak_pos in get_peaks(self.mmr_size) {
+            peaks.push(self.get_stored_elem(peak_pos).await?);
+        }
+        B::bag_peaks::<M>(peaks, self.mmr_size)
+    }
+
+    /// Generate merkle proof for positions, positions of proof items are calculated first,
+    /// then the items are fetched from store
+    pub async fn gen_proof(&self, pos_list: Vec<u64>) -> Result<MerkleProof<T, M, B>> {
+        let (proof_positions, bagging_track) = gen_proof_positions(self.mmr_size, pos_list)?;
+        let mut proof: Vec<T> = Vec::with_capacity(proof_positions.len());
+        for pos in proof_positions {
+            proof.push(self.get_stored_elem(pos).await?);
+        }
+        if bagging_track > 1 {
+            let rhs_peaks = proof.split_off(proof.len() - bagging_track);
+            proof.extend(B::bag_rhs_peaks::<M>(rhs_peaks)?);
+        }
+        Ok(MerkleProof::new(self.mmr_size, proof))
+    }
+
+    pub async fn commit(self) -> Result<()> {
+        let Self {
+            mut store,
+            memory_batch,
+            ..
+        } = self;
+        for (pos, elems) in memory_batch {
+            store.append(pos, elems).await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+//! Peak bagging
+//!
+//! The MMR root is calculated by bagging peaks. Peaks are bagged from right to left
+//! via `merge(right, left)` by default. Other MMR systems bag peaks from left to right,
+//! commit the MMR size to the root, or use the peak list as the root.
+
+use crate::helper::get_peaks;
+use crate::vec;
+use crate::vec::Vec;
+use crate::{Error, NodeMerge, Result};
+use core::marker::PhantomData;
+
+/// Strategy of bagging peaks into the MMR root
+pub trait PeakBagging<T> {
+    type Root;
+
+    /// Bag peaks ordered from left to right into the root.
+    /// If `bag_rhs_peaks` compresses peaks, the last peak may be the bagged right hand side peaks
+    fn bag_peaks<M: NodeMerge<Item = T>>(peaks: Vec<T>, mmr_size: u64) -> Result<Self::Root>;
+
+    /// Compress peaks on the right hand side of proven positions, the result is put into proof.
+    /// Peaks are kept as they are by default.
+    fn bag_rhs_peaks<M: NodeMerge<Item = T>>(rhs_peaks: Vec<T>) -> Result<Vec<T>> {
+        Ok(rhs_peaks)
+    }
+}
+
+/// Commit the MMR size to the root
+pub trait CommitMMRSize {
+    type Item;
+    fn commit_mmr_size(root: &Self::Item, mmr_size: u64) -> Result<Self::Item>;
+}
+
+fn bag_right_to_left<T, M: NodeMerge<Item = T>>(mut peaks: Vec<T>) -> Result<T> {
+    // bagging from right to left via hash(right, left).
+    while peaks.len() > 1 {
+        let right_peak = peaks.pop().expect("pop");
+        let left_peak = peaks.pop().expect("pop");
+        peaks.push(M::merge_peaks(&right_peak, &left_peak)?);
+    }
+    peaks.pop().ok_or(Error::CorruptedProof)
+}
+
+// strategies which don't compress rhs peaks need every peak
+fn ensure_all_peaks<T>(peaks: &[T], mmr_size: u64) -> Result<()> {
+    if peaks.len() != get_peaks(mmr_size).len() {
+        return Err(Error::CorruptedProof);
+    }
+    Ok(())
+}
+
+/// Bag peaks from right to left via `merge(right, left)`, the default strategy
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RightToLeftBagging;
+
+impl<T> PeakBagging<T> for RightToLeftBagging {
+    type Root = T;
+
+    fn bag_peaks<M: NodeMerge<Item = T>>(peaks: Vec<T>, _mmr_size: u64) -> Result<T> {
+        bag_right_to_left::<_, M>(peaks)
+    }
+
+    fn bag_rhs_peaks<M: NodeMerge<Item = T>>(rhs_peaks: Vec<T>) -> Result<Vec<T>> {
+        Ok(vec![bag_right_to_left::<_, M>(rhs_peaks)?])
+    }
+}
+
+/// Bag peaks from left to right via `merge(left, right)`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LeftToRightBagging;
+
+impl<T> PeakBagging<T> for LeftToRightBagging {
+    type Root = T;
+
+    fn bag_peaks<M: NodeMerge<Item = T>>(peaks: Vec<T>, mmr_size: u64) -> Result<T> {
+        ensure_all_peaks(&peaks, mmr_size)?;
+        let mut peaks = peaks.into_iter();
+        let mut root = peaks.next().ok_or(Error::CorruptedProof)?;
+        for peak in peaks {
+            root = M::merge_peaks(&root, &peak)?;
+        }
+        Ok(root)
+    }
+}
+
+/// Bag peaks from right to left, then commit the MMR size to the root
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MMRSizeBagging<C>(PhantomData<C>);
+
+impl<T, C: CommitMMRSize<Item = T>> PeakBagging<T> for MMRSizeBagging<C> {
+    type Root = T;
+
+    fn bag_peaks<M: NodeMerge<Item = T>>(peaks: Vec<T>, mmr_size: u64) -> Result<T> {
+        let root = bag_right_to_left::<_, M>(peaks)?;
+        C::commit_mmr_size(&root, mmr_size)
+    }
+
+    fn bag_rhs_peaks<M: NodeMerge<Item = T>>(rhs_peaks: Vec<T>) -> Result<Vec<T>> {
+        Ok(vec![bag_right_to_left::<_, M>(rhs_peaks)?])
+    }
+}
+
+/// Use the peak list as the root
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PeakListBagging;
+
+impl<T> PeakBagging<T> for PeakListBagging {
+    type Root = Vec<T>;
+
+    fn bag_peaks<M: NodeMerge<Item = T>>(peaks: Vec<T>, mmr_size: u64) -> Result<Vec<T>> {
+        ensure_all_peaks(&peaks, mmr_size)?;
+        Ok(peaks)
+    }
+}
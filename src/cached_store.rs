@@ -0,0 +1,243 @@
+//! Cached store
+//!
+//! `CachedStore` wraps any `MMRStore` with a bounded LRU cache of elems.
+//! Nodes at or above `pin_height` are touched by almost every proof and root,
+//! they are pinned in cache and never evicted. Pinned nodes share the capacity
+//! with the others, so the cache never holds more than `capacity` elems.
+
+use crate::collections::BTreeMap;
+use crate::helper::pos_height_in_tree;
+use crate::vec::Vec;
+use crate::{MMRStore, Result};
+use core::cell::{Cell, RefCell};
+
+struct LruCache<T> {
+    capacity: usize,
+    // position -> (elem, last access tick)
+    elems: BTreeMap<u64, (T, u64)>,
+    // last access tick -> position, the first one is evicted first
+    access: BTreeMap<u64, u64>,
+    tick: u64,
+}
+
+impl<T: Clone> LruCache<T> {
+    fn new(capacity: usize) -> Self {
+        LruCache {
+            capacity,
+            elems: BTreeMap::new(),
+            access: BTreeMap::new(),
+            tick: 0,
+        }
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    fn get(&mut self, pos: u64) -> Option<T> {
+        let tick = self.next_tick();
+        let (elem, last_tick) = self.elems.get_mut(&pos)?;
+        self.access.remove(last_tick);
+        self.access.insert(tick, pos);
+        *last_tick = tick;
+        Some(elem.clone())
+    }
+
+    fn evict_oldest(&mut self) {
+        if let Some((&oldest_tick, &oldest_pos)) = self.access.iter().next() {
+            self.access.remove(&oldest_tick);
+            self.elems.remove(&oldest_pos);
+        }
+    }
+
+    // evict the least recently used elems until they fit in `capacity`
+    fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.elems.len() > capacity {
+            self.evict_oldest();
+        }
+    }
+
+    fn insert(&mut self, pos: u64, elem: T) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.remove(pos);
+        if self.elems.len() >= self.capacity {
+            self.evict_oldest();
+        }
+        let tick = self.next_tick();
+        self.access.insert(tick, pos);
+        self.elems.insert(pos, (elem, tick));
+    }
+
+    fn remove(&mut self, pos: u64) {
+        if let Some((_, tick)) = self.elems.remove(&pos) {
+            self.access.remove(&tick);
+        }
+    }
+
+    fn truncate(&mut self, mmr_size: u64) {
+        for (_, tick) in self.elems.split_off(&mmr_size).into_values() {
+            self.access.remove(&tick);
+        }
+    }
+}
+
+pub struct CachedStore<T, S> {
+    store: RefCell<S>,
+    cache: RefCell<LruCache<T>>,
+    pinned: RefCell<BTreeMap<u64, T>>,
+    capacity: usize,
+    pin_height: u32,
+    hits: Cell<u64>,
+    misses: Cell<u64>,
+}
+
+impl<T: Clone, S: MMRStore<T>> CachedStore<T, S> {
+    /// Cache at most `capacity` elems, elems at or above `pin_height` are pinned.
+    /// Pinned elems take the room of the others until all of `capacity` is pinned,
+    /// elems read after that are not cached. `pin_height` 0 pins every elem,
+    /// so the cache keeps the first `capacity` elems read.
+    pub fn new(store: S, capacity: usize, pin_height: u32) -> Self {
+        CachedStore {
+            store: RefCell::new(store),
+            cache: RefCell::new(LruCache::new(capacity)),
+            pinned: RefCell::new(BTreeMap::new()),
+            capacity,
+            pin_height,
+            hits: Cell::new(0),
+            misses: Cell::new(0),
+        }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.store.into_inner()
+    }
+
+    /// Count of cached elems, pinned ones included
+    pub fn len(&self) -> usize {
+        self.pinned.borrow().len() + self.cache.borrow().elems.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Count of reads served by cache
+    pub fn hits(&self) -> u64 {
+        self.hits.get()
+    }
+
+    /// Count of reads served by the inner store
+    pub fn misses(&self) -> u64 {
+        self.misses.get()
+    }
+
+    fn get_cached(&self, pos: u64) -> Option<T> {
+        match self.pinned.borrow().get(&pos) {
+            Some(elem) => Some(elem.clone()),
+            None => self.cache.borrow_mut().get(pos),
+        }
+    }
+
+    fn cache_elem(&self, pos: u64, elem: T) {
+        let mut pinned = self.pinned.borrow_mut();
+        let mut cache = self.cache.borrow_mut();
+        if pos_height_in_tree(pos) >= self.pin_height
+            && (pinned.len() < self.capacity || pinned.contains_key(&pos))
+        {
+            pinned.insert(pos, elem);
+            cache.remove(pos);
+            cache.set_capacity(self.capacity - pinned.len());
+        } else {
+            cache.insert(pos, elem);
+        }
+    }
+
+    // the room left by pinned elems is given back to the others
+    fn release_pinned(&self) {
+        let pinned = self.pinned.borrow();
+        self.cache
+            .borrow_mut()
+            .set_capacity(self.capacity - pinned.len());
+    }
+}
+
+impl<T: Clone, S: MMRStore<T>> MMRStore<T> for &CachedStore<T, S> {
+    fn get_elem(&self, pos: u64) -> Result<Option<T>> {
+        let cached = self.get_cached(pos);
+        if cached.is_some() {
+            self.hits.set(self.hits.get() + 1);
+            return Ok(cached);
+        }
+        self.misses.set(self.misses.get() + 1);
+        let elem = self.store.borrow().get_elem(pos)?;
+        if let Some(elem) = &elem {
+            self.cache_elem(pos, elem.clone());
+        }
+        Ok(elem)
+    }
+
+    /// Read missed elems from the inner store in one batch
+    fn get_elems(&self, pos_list: &[u64]) -> Result<Vec<Option<T>>> {
+        let mut elems: Vec<_> = pos_list.iter().map(|&pos| self.get_cached(pos)).collect();
+        let missed_pos_list: Vec<u64> = pos_list
+            .iter()
+            .zip(&elems)
+            .filter(|(_, elem)| elem.is_none())
+            .map(|(&pos, _)| pos)
+            .collect();
+        self.hits
+            .set(self.hits.get() + (pos_list.len() - missed_pos_list.len()) as u64);
+        if missed_pos_list.is_empty() {
+            return Ok(elems);
+        }
+        self.misses
+            .set(self.misses.get() + missed_pos_list.len() as u64);
+        let missed_elems = self.store.borrow().get_elems(&missed_pos_list)?;
+        let missed = missed_pos_list.into_iter().zip(missed_elems);
+        let unfilled = elems.iter_mut().filter(|elem| elem.is_none());
+        for (elem, (pos, missed_elem)) in unfilled.zip(missed) {
+            if let Some(missed_elem) = &missed_elem {
+                self.cache_elem(pos, missed_elem.clone());
+            }
+            *elem = missed_elem;
+        }
+        Ok(elems)
+    }
+
+    /// Write through to the inner store, appended elems are cached
+    fn append(&mut self, pos: u64, elems: Vec<T>) -> Result<()> {
+        self.store.borrow_mut().append(pos, elems.clone())?;
+        for (i, elem) in elems.into_iter().enumerate() {
+            self.cache_elem(pos + i as u64, elem);
+        }
+        Ok(())
+    }
+
+    fn truncate(&mut self, mmr_size: u64) -> Result<()> {
+        self.pinned.borrow_mut().split_off(&mmr_size);
+        self.cache.borrow_mut().truncate(mmr_size);
+        self.release_pinned();
+        self.store.borrow_mut().truncate(mmr_size)
+    }
+
+    fn remove(&mut self, pos_list: Vec<u64>) -> Result<()> {
+        {
+            let mut pinned = self.pinned.borrow_mut();
+            let mut cache = self.cache.borrow_mut();
+            for &pos in &pos_list {
+                pinned.remove(&pos);
+                cache.remove(pos);
+            }
+        }
+        self.release_pinned();
+        self.store.borrow_mut().remove(pos_list)
+    }
+
+    fn commit(&mut self) -> Result<()> {
+        self.store.borrow_mut().commit()
+    }
+}
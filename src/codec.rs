@@ -0,0 +1,153 @@
+//! Binary codec
+//!
+//! Canonical encoding of MerkleProof, elems are encoded by a pluggable ElemCodec.
+//!
+//! Encoding of MerkleProof (version 1):
+//!
+//! | field      | size                        |
+//! |------------|-----------------------------|
+//! | version    | 1 byte                      |
+//! | mmr_size   | 8 bytes, little endian      |
+//! | item count | 4 bytes, little endian      |
+//! | items      | encoded by `ElemCodec`      |
+
+use crate::vec::Vec;
+use crate::{Error, MerkleProof, NodeMerge, PeakBagging, Result};
+use core::convert::TryInto;
+use core::fmt::Debug;
+
+pub const MERKLE_PROOF_ENCODING_VERSION: u8 = 1;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum CodecError {
+    /// Unknown encoding version
+    UnsupportedVersion(u8),
+    /// Input ends before all fields are decoded
+    Truncated,
+    /// Input has bytes left after decoding, or declares more items than possible
+    Oversized,
+    /// Bytes are not a valid elem
+    InvalidElem,
+}
+
+impl From<CodecError> for Error {
+    fn from(err: CodecError) -> Self {
+        Error::Codec(err)
+    }
+}
+
+/// Encode and decode elems of MMR, the encoding can be of variable size
+/// as long as `decode` knows where an item ends
+pub trait ElemCodec {
+    type Item;
+    /// Append encoded item to `buf`
+    fn encode(item: &Self::Item, buf: &mut Vec<u8>);
+    /// Decode an item from the head of `bytes`, return the item and the count of consumed bytes
+    fn decode(bytes: &[u8]) -> Result<(Self::Item, usize)>;
+
+    fn encode_to_vec(item: &Self::Item) -> Vec<u8> {
+        let mut buf = Vec::new();
+        Self::encode(item, &mut buf);
+        buf
+    }
+
+    /// Decode an item from all of `bytes`
+    fn decode_all(bytes: &[u8]) -> Result<Self::Item> {
+        let (item, len) = Self::decode(bytes)?;
+        if len != bytes.len() {
+            return Err(CodecError::Oversized.into());
+        }
+        Ok(item)
+    }
+}
+
+/// Elems encoded in exactly `SIZE` bytes, used by stores of fixed-size records
+pub trait FixedSizeElemCodec: ElemCodec {
+    const SIZE: usize;
+}
+
+/// Codec of `[u8; N]`, such as 32 bytes hashes, encoded as they are
+pub struct FixedBytesCodec<const N: usize>;
+
+impl<const N: usize> ElemCodec for FixedBytesCodec<N> {
+    type Item = [u8; N];
+    fn encode(item: &Self::Item, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(item);
+    }
+    fn decode(bytes: &[u8]) -> Result<(Self::Item, usize)> {
+        let item = bytes
+            .get(..N)
+            .ok_or(CodecError::Truncated)?
+            .try_into()
+            .expect("N bytes");
+        Ok((item, N))
+    }
+}
+
+impl<const N: usize> FixedSizeElemCodec for FixedBytesCodec<N> {
+    const SIZE: usize = N;
+}
+
+/// Codec of `Vec<u8>`, encoded as length (4 bytes, little endian) followed by bytes
+pub struct BytesCodec;
+
+impl ElemCodec for BytesCodec {
+    type Item = Vec<u8>;
+    fn encode(item: &Self::Item, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&(item.len() as u32).to_le_bytes());
+        buf.extend_from_slice(item);
+    }
+    fn decode(mut bytes: &[u8]) -> Result<(Self::Item, usize)> {
+        let len = u32::from_le_bytes(take(&mut bytes, 4)?.try_into().expect("4 bytes")) as usize;
+        let item = take(&mut bytes, len)?.to_vec();
+        Ok((item, 4 + len))
+    }
+}
+
+// split `len` bytes from the head of `bytes`
+fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+    if bytes.len() < len {
+        return Err(CodecError::Truncated.into());
+    }
+    let (head, rest) = bytes.split_at(len);
+    *bytes = rest;
+    Ok(head)
+}
+
+impl<T: PartialEq + Debug + Clone, M: NodeMerge<Item = T>, B: PeakBagging<T>> MerkleProof<T, M, B> {
+    pub fn encode<C: ElemCodec<Item = T>>(&self) -> Vec<u8> {
+        let items = self.proof_items();
+        let mut buf = Vec::with_capacity(13);
+        buf.push(MERKLE_PROOF_ENCODING_VERSION);
+        buf.extend_from_slice(&self.mmr_size().to_le_bytes());
+        buf.extend_from_slice(&(items.len() as u32).to_le_bytes());
+        for item in items {
+            C::encode(item, &mut buf);
+        }
+        buf
+    }
+
+    pub fn decode<C: ElemCodec<Item = T>>(mut bytes: &[u8]) -> Result<Self> {
+        let version = take(&mut bytes, 1)?[0];
+        if version != MERKLE_PROOF_ENCODING_VERSION {
+            return Err(CodecError::UnsupportedVersion(version).into());
+        }
+        let mmr_size = u64::from_le_bytes(take(&mut bytes, 8)?.try_into().expect("8 bytes"));
+        let count = u32::from_le_bytes(take(&mut bytes, 4)?.try_into().expect("4 bytes"));
+        // a proof never has more items than nodes of the MMR
+        if u64::from(count) > mmr_size {
+            return Err(CodecError::Oversized.into());
+        }
+        // don't trust count to allocate memory
+        let mut items = Vec::with_capacity((count as usize).min(bytes.len()));
+        for _ in 0..count {
+            let (item, len) = C::decode(bytes)?;
+            take(&mut bytes, len)?;
+            items.push(item);
+        }
+        if !bytes.is_empty() {
+            return Err(CodecError::Oversized.into());
+        }
+        Ok(MerkleProof::new(mmr_size, items))
+    }
+}
@@ -1,14 +1,44 @@
 pub type Result<T> = core::result::Result<T, Error>;
 
+/// Errors of MMR, variants carrying a `String` only exist with the `alloc` feature,
+/// so the enum is non-exhaustive and matches on it need a wildcard arm
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[non_exhaustive]
 pub enum Error {
     GetRootOnEmpty,
     InconsistentStore,
+    #[cfg(feature = "alloc")]
     StoreError(crate::string::String),
     /// proof items is not enough to build a tree
     CorruptedProof,
     /// The leaves is an empty list, or beyond the mmr range
     GenProofForInvalidLeaves,
+    /// The mmr_size is not the size of any MMR, or it is out of range
+    InvalidMMRSize,
+    /// The leaves are pruned, can't generate proof for them
+    GenProofForPrunedLeaves,
+    /// The position to prune is beyond the mmr range
+    PruneInvalidPosition,
+    /// Rewind to a size whose nodes are already removed by pruning
+    RewindPrunedNodes,
+    /// Failed to encode or decode bytes
+    #[cfg(feature = "alloc")]
+    Codec(crate::codec::CodecError),
+    /// Failed to merge elems
+    #[cfg(feature = "alloc")]
+    MergeError(crate::string::String),
+    /// The position is not a leaf
+    InvalidLeafPosition,
+    /// The fixed-capacity buffer of an allocation-free verifier is too small
+    BufferTooSmall,
+    /// The weight is not less than the total weight of MMR
+    WeightOutOfRange,
+    /// The node at the position is missing in store
+    MissingNode(u64),
+    /// The node at the position doesn't equal the merge of its children
+    InvalidNode(u64),
+    /// The delta of a sample distribution is not in `(0, 1]`
+    InvalidDelta,
 }
 
 impl core::fmt::Display for Error {
@@ -17,9 +47,24 @@ impl core::fmt::Display for Error {
         match self {
             GetRootOnEmpty => write!(f, "Get root on an empty MMR")?,
             InconsistentStore => write!(f, "Inconsistent store")?,
+            #[cfg(feature = "alloc")]
             StoreError(msg) => write!(f, "Store error {}", msg)?,
             CorruptedProof => write!(f, "Corrupted proof")?,
             GenProofForInvalidLeaves => write!(f, "Generate proof ofr invalid leaves")?,
+            InvalidMMRSize => write!(f, "Invalid MMR size")?,
+            GenProofForPrunedLeaves => write!(f, "Generate proof for pruned leaves")?,
+            PruneInvalidPosition => write!(f, "Prune invalid position")?,
+            RewindPrunedNodes => write!(f, "Rewind to pruned nodes")?,
+            #[cfg(feature = "alloc")]
+            Codec(err) => write!(f, "Codec error {:?}", err)?,
+            #[cfg(feature = "alloc")]
+            MergeError(msg) => write!(f, "Merge error {}", msg)?,
+            InvalidLeafPosition => write!(f, "Invalid leaf position")?,
+            BufferTooSmall => write!(f, "Buffer too small")?,
+            WeightOutOfRange => write!(f, "Weight out of range")?,
+            MissingNode(pos) => write!(f, "Missing node at {}", pos)?,
+            InvalidNode(pos) => write!(f, "Invalid node at {}", pos)?,
+            InvalidDelta => write!(f, "Invalid delta")?,
         }
         Ok(())
     }
@@ -0,0 +1,247 @@
+//! File store
+//!
+//! An append-only `MMRStore` of fixed-size elems, elems are encoded by a `FixedSizeElemCodec`.
+//!
+//! The store is a directory of files:
+//!
+//! | file          | content                                                   |
+//! |---------------|-----------------------------------------------------------|
+//! | `mmr.data`    | elems of `C::SIZE` bytes, ordered by position             |
+//! | `mmr.size`    | committed MMR size, 8 bytes, little endian                |
+//! | `mmr.journal` | elems replacing committed ones from a position, if exists |
+//!
+//! Appended elems are readable at once, but only persisted by `commit`, which syncs
+//! `mmr.data` then atomically replaces `mmr.size`. Elems beyond the committed size are
+//! left by a crash and dropped when the store is opened. `MMR::commit` commits the store
+//! after writing the batch.
+//!
+//! A truncate below the committed size is pending until `commit` as well, elems appended
+//! after it are kept in memory, so committed elems are never overwritten before commit.
+//! `commit` writes them to `mmr.journal` first, which is the start position, 8 bytes,
+//! little endian, followed by the elems, then applies the journal to `mmr.data` and
+//! `mmr.size`. A crash after the journal is written is repaired by replaying it on open.
+
+use crate::codec::CodecError;
+use crate::{Error, FixedSizeElemCodec, MMRStore, Result};
+use std::cell::RefCell;
+use std::convert::TryInto;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+const DATA_FILE: &str = "mmr.data";
+const SIZE_FILE: &str = "mmr.size";
+const SIZE_TMP_FILE: &str = "mmr.size.tmp";
+const JOURNAL_FILE: &str = "mmr.journal";
+const JOURNAL_TMP_FILE: &str = "mmr.journal.tmp";
+
+fn io_error(err: io::Error) -> Error {
+    Error::StoreError(err.to_string())
+}
+
+// replace a file atomically via rename
+fn write_atomically(dir: &Path, tmp_file: &str, file: &str, content: &[u8]) -> Result<()> {
+    let tmp_path = dir.join(tmp_file);
+    let mut tmp = File::create(&tmp_path).map_err(io_error)?;
+    tmp.write_all(content).map_err(io_error)?;
+    tmp.sync_all().map_err(io_error)?;
+    fs::rename(&tmp_path, dir.join(file)).map_err(io_error)?;
+    sync_dir(dir)
+}
+
+// persist renames and removes in the directory
+fn sync_dir(_dir: &Path) -> Result<()> {
+    #[cfg(unix)]
+    File::open(_dir)
+        .and_then(|dir| dir.sync_all())
+        .map_err(io_error)?;
+    Ok(())
+}
+
+// write elems of the journal from `start` and drop elems after them, returns the new size
+fn apply_journal(dir: &Path, data: &File, elem_size: usize, journal: &[u8]) -> Result<u64> {
+    if journal.len() < 8 || !(journal.len() - 8).is_multiple_of(elem_size) {
+        return Err(Error::StoreError("invalid journal file".into()));
+    }
+    let (start, elems) = journal.split_at(8);
+    let start = u64::from_le_bytes(start.try_into().expect("8 bytes"));
+    let size = start + (elems.len() / elem_size) as u64;
+    let mut data = data;
+    data.seek(SeekFrom::Start(start * elem_size as u64))
+        .map_err(io_error)?;
+    data.write_all(elems).map_err(io_error)?;
+    data.set_len(size * elem_size as u64).map_err(io_error)?;
+    data.sync_data().map_err(io_error)?;
+    write_atomically(dir, SIZE_TMP_FILE, SIZE_FILE, &size.to_le_bytes())?;
+    fs::remove_file(dir.join(JOURNAL_FILE)).map_err(io_error)?;
+    sync_dir(dir)?;
+    Ok(size)
+}
+
+struct Inner {
+    data: File,
+    // count of elems, including uncommitted ones
+    size: u64,
+    // count of elems in size file
+    committed_size: u64,
+    // a pending truncate below the committed size, elems from the position
+    // are kept in the buffer and written by commit
+    rewrite: Option<(u64, Vec<u8>)>,
+}
+
+pub struct FileStore<T, C> {
+    dir: PathBuf,
+    inner: RefCell<Inner>,
+    codec: PhantomData<(T, C)>,
+}
+
+impl<T, C: FixedSizeElemCodec<Item = T>> FileStore<T, C> {
+    /// Open the store in `dir`, create an empty one if `dir` has no store
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir).map_err(io_error)?;
+        let size = match fs::read(dir.join(SIZE_FILE)) {
+            Ok(bytes) => u64::from_le_bytes(
+                bytes[..]
+                    .try_into()
+                    .map_err(|_| Error::StoreError("invalid size file".into()))?,
+            ),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => 0,
+            Err(err) => return Err(io_error(err)),
+        };
+        let data = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(dir.join(DATA_FILE))
+            .map_err(io_error)?;
+        // replay the journal of an interrupted commit
+        let size = match fs::read(dir.join(JOURNAL_FILE)) {
+            Ok(journal) => apply_journal(&dir, &data, C::SIZE, &journal)?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => size,
+            Err(err) => return Err(io_error(err)),
+        };
+        let data_len = size * C::SIZE as u64;
+        if data.metadata().map_err(io_error)?.len() < data_len {
+            return Err(Error::InconsistentStore);
+        }
+        // drop uncommitted elems
+        data.set_len(data_len).map_err(io_error)?;
+        Ok(FileStore {
+            dir,
+            inner: RefCell::new(Inner {
+                data,
+                size,
+                committed_size: size,
+                rewrite: None,
+            }),
+            codec: PhantomData,
+        })
+    }
+
+    /// Count of elems, including uncommitted ones
+    pub fn mmr_size(&self) -> u64 {
+        self.inner.borrow().size
+    }
+
+    /// Persist appended elems and truncates, a crash after commit keeps them
+    pub fn commit(&self) -> Result<()> {
+        let mut inner = self.inner.borrow_mut();
+        match &inner.rewrite {
+            Some((start, elems)) => {
+                let mut journal = start.to_le_bytes().to_vec();
+                journal.extend_from_slice(elems);
+                write_atomically(&self.dir, JOURNAL_TMP_FILE, JOURNAL_FILE, &journal)?;
+                apply_journal(&self.dir, &inner.data, C::SIZE, &journal)?;
+                // keep pending elems until they are in data file, so a failed commit can be retried
+                inner.rewrite = None;
+            }
+            None => {
+                inner.data.sync_data().map_err(io_error)?;
+                write_atomically(
+                    &self.dir,
+                    SIZE_TMP_FILE,
+                    SIZE_FILE,
+                    &inner.size.to_le_bytes(),
+                )?;
+            }
+        }
+        inner.committed_size = inner.size;
+        Ok(())
+    }
+}
+
+impl<T, C: FixedSizeElemCodec<Item = T>> MMRStore<T> for &FileStore<T, C> {
+    fn get_elem(&self, pos: u64) -> Result<Option<T>> {
+        let mut inner = self.inner.borrow_mut();
+        if pos >= inner.size {
+            return Ok(None);
+        }
+        if let Some((start, elems)) = &inner.rewrite {
+            if pos >= *start {
+                let offset = (pos - start) as usize * C::SIZE;
+                return C::decode_all(&elems[offset..offset + C::SIZE]).map(Some);
+            }
+        }
+        let mut buf = vec![0u8; C::SIZE];
+        inner
+            .data
+            .seek(SeekFrom::Start(pos * C::SIZE as u64))
+            .map_err(io_error)?;
+        inner.data.read_exact(&mut buf).map_err(io_error)?;
+        C::decode_all(&buf).map(Some)
+    }
+
+    fn append(&mut self, pos: u64, elems: Vec<T>) -> Result<()> {
+        let mut inner = self.inner.borrow_mut();
+        if pos != inner.size {
+            return Err(Error::StoreError("file store is append-only".into()));
+        }
+        let mut buf = Vec::with_capacity(elems.len() * C::SIZE);
+        for elem in &elems {
+            let offset = buf.len();
+            C::encode(elem, &mut buf);
+            if buf.len() - offset != C::SIZE {
+                return Err(CodecError::InvalidElem.into());
+            }
+        }
+        if let Some((_, rewrite)) = &mut inner.rewrite {
+            // committed elems are replaced on commit
+            rewrite.extend(buf);
+        } else {
+            // overwrite partially written elems of a failed append
+            inner
+                .data
+                .seek(SeekFrom::Start(pos * C::SIZE as u64))
+                .map_err(io_error)?;
+            inner.data.write_all(&buf).map_err(io_error)?;
+        }
+        inner.size += elems.len() as u64;
+        Ok(())
+    }
+
+    /// Remove elems at or above `mmr_size`, the new size is persisted by `commit`
+    fn truncate(&mut self, mmr_size: u64) -> Result<()> {
+        let mut inner = self.inner.borrow_mut();
+        if mmr_size >= inner.size {
+            return Ok(());
+        }
+        let committed_size = inner.committed_size;
+        match &mut inner.rewrite {
+            Some((start, rewrite)) if mmr_size >= *start => {
+                rewrite.truncate((mmr_size - *start) as usize * C::SIZE);
+            }
+            // uncommitted elems in data file are overwritten by later appends
+            None if mmr_size >= committed_size => {}
+            rewrite => *rewrite = Some((mmr_size, Vec::new())),
+        }
+        inner.size = mmr_size;
+        Ok(())
+    }
+
+    fn commit(&mut self) -> Result<()> {
+        FileStore::commit(*self)
+    }
+}
@@ -1,69 +1,88 @@
+#[cfg(feature = "alloc")]
+use crate::vec;
+#[cfg(feature = "alloc")]
 use crate::vec::Vec;
+use crate::{Error, Result};
 
-fn log2(mut n: u64) -> u64 {
-    let mut k = 0;
-    while n > 1 {
-        k += 1;
-        n >>= 1;
-    }
-    k
+/// `index` must be at most `2^63`, see `leaf_count_to_mmr_size`
+pub fn leaf_index_to_pos(index: u64) -> u64 {
+    // leaf is pushed to an MMR with `index` leaves
+    leaf_count_to_mmr_size(index)
 }
 
-pub fn leaf_index_to_pos(index: u64) -> u64 {
-    if index == 0 {
-        return 0;
-    }
-    // leaf_count
-    let mut leaves = index + 1;
-    let mut tree_node_count = 0;
-    let mut height = 0u32;
-    while leaves > 1 {
-        // get heighest peak height
-        height = log2(leaves) as u32;
-        // calculate leaves in peak
-        let peak_leaves = 1 << height;
-        // heighest positon
-        let sub_tree_node_count = get_peak_pos_by_height(height) + 1;
-        tree_node_count += sub_tree_node_count;
-        leaves -= peak_leaves;
+/// `index` must be less than `2^63`, see `leaf_count_to_mmr_size`
+pub fn leaf_index_to_mmr_size(index: u64) -> u64 {
+    leaf_count_to_mmr_size(index + 1)
+}
+
+pub fn pos_height_in_tree(pos: u64) -> u32 {
+    // in 1-based positions the root of a perfect tree is all ones, a node keeps its
+    // height when the perfect tree on its left, `2^k - 1` nodes for the highest bit `k`,
+    // is skipped, so jump once per peak on the left until the position is all ones
+    let mut pos = pos + 1;
+    while pos.trailing_ones() + pos.leading_zeros() != u64::BITS {
+        pos -= (1 << (u64::BITS - 1 - pos.leading_zeros())) - 1;
     }
-    // two leaves can construct a new peak, the only valid number of leaves is 0 or 1.
-    debug_assert!(leaves == 0 || leaves == 1, "remain leaves incorrect");
-    if leaves == 1 {
-        // add one pos for remain leaf
-        // equals to `tree_node_count - 1 + 1`
-        tree_node_count
+    pos.trailing_ones() - 1
+}
+
+// node count of the highest perfect tree which fits in `size`, `size` must not be zero
+fn highest_peak_size(size: u64) -> u64 {
+    let peak_size = u64::MAX >> size.leading_zeros();
+    if peak_size > size {
+        peak_size >> 1
     } else {
-        let pos = tree_node_count - 1;
-        pos - u64::from(height)
+        peak_size
     }
 }
 
-// TODO optimize
-pub fn leaf_index_to_mmr_size(index: u64) -> u64 {
-    let mut pos = leaf_index_to_pos(index);
-    while pos_height_in_tree(pos + 1) > pos_height_in_tree(pos) {
-        pos += 1
-    }
-    pos + 1
+/// Returns true if `mmr_size` equals the size of an MMR with some number of leaves,
+/// that is, it can be split into perfect trees of strictly decreasing heights.
+pub fn is_valid_mmr_size(mmr_size: u64) -> bool {
+    mmr_size_to_leaf_count(mmr_size).is_ok()
 }
 
-pub fn pos_height_in_tree(mut pos: u64) -> u32 {
-    pos += 1;
-    fn all_ones(num: u64) -> bool {
-        num != 0 && num.count_zeros() == num.leading_zeros()
-    }
-    fn jump_left(pos: u64) -> u64 {
-        let bit_length = 64 - pos.leading_zeros();
-        let most_significant_bits = 1 << (bit_length - 1);
-        pos - (most_significant_bits - 1)
+/// Returns the count of leaves in an MMR of `mmr_size`
+pub fn mmr_size_to_leaf_count(mut mmr_size: u64) -> Result<u64> {
+    let mut leaf_count = 0;
+    let mut prev_peak_size = 0;
+    while mmr_size > 0 {
+        let peak_size = highest_peak_size(mmr_size);
+        // peaks must have strictly decreasing heights
+        if prev_peak_size != 0 && peak_size >= prev_peak_size {
+            return Err(Error::InvalidMMRSize);
+        }
+        // a perfect tree of 2^(h+1) - 1 nodes has 2^h leaves
+        leaf_count += (peak_size >> 1) + 1;
+        mmr_size -= peak_size;
+        prev_peak_size = peak_size;
     }
+    Ok(leaf_count)
+}
 
-    while !all_ones(pos) {
-        pos = jump_left(pos)
-    }
+/// Max count of leaves of an MMR whose size fits in `u64`, the size is `u64::MAX`
+pub const MAX_LEAF_COUNT: u64 = 1 << 63;
+
+/// Returns the size of an MMR with `leaf_count` leaves,
+/// `leaf_count` must be at most `MAX_LEAF_COUNT`, otherwise the size overflows
+pub fn leaf_count_to_mmr_size(leaf_count: u64) -> u64 {
+    debug_assert!(
+        leaf_count <= MAX_LEAF_COUNT,
+        "leaf count {} is out of MMR size",
+        leaf_count
+    );
+    // each peak of 2^h leaves has 2^(h+1) - 1 nodes,
+    // subtract first so the size of `MAX_LEAF_COUNT` leaves doesn't overflow
+    leaf_count - u64::from(leaf_count.count_ones()) + leaf_count
+}
 
-    64 - pos.leading_zeros() - 1
+/// Returns the leaf index of a leaf position
+pub fn pos_to_leaf_index(pos: u64) -> Result<u64> {
+    if pos_height_in_tree(pos) != 0 {
+        return Err(Error::InvalidLeafPosition);
+    }
+    // leaf is pushed to an MMR of `pos` size
+    mmr_size_to_leaf_count(pos)
 }
 
 pub fn parent_offset(height: u32) -> u64 {
@@ -74,49 +93,31 @@ pub fn sibling_offset(height: u32) -> u64 {
     (2 << height) - 1
 }
 
+#[cfg(feature = "alloc")]
 pub fn get_peaks(mmr_size: u64) -> Vec<u64> {
-    let mut pos_s = Vec::new();
-    let (mut height, mut pos) = left_peak_height_pos(mmr_size);
-    pos_s.push(pos);
-    while height > 0 {
-        let peak = match get_right_peak(height, pos, mmr_size) {
-            Some(peak) => peak,
-            None => break,
-        };
-        height = peak.0;
-        pos = peak.1;
-        pos_s.push(pos);
+    // keep the behaviour of the empty MMR
+    if mmr_size == 0 {
+        return vec![0];
     }
-    pos_s
+    peaks_iter(mmr_size).collect()
 }
 
-fn get_right_peak(mut height: u32, mut pos: u64, mmr_size: u64) -> Option<(u32, u64)> {
-    // move to right sibling pos
-    pos += sibling_offset(height);
-    // loop until we find a pos in mmr
-    while pos > mmr_size - 1 {
-        if height == 0 {
+/// Iterate peaks from left to right without allocation, an empty MMR has no peaks
+pub fn peaks_iter(mmr_size: u64) -> impl Iterator<Item = u64> {
+    let mut pos_offset = 0;
+    let mut remaining = mmr_size;
+    // take the highest perfect tree from the remaining nodes, each loop finds a peak
+    core::iter::from_fn(move || {
+        if remaining == 0 {
             return None;
         }
-        // move to left child
-        pos -= parent_offset(height - 1);
-        height -= 1;
-    }
-    Some((height, pos))
-}
-
-fn get_peak_pos_by_height(height: u32) -> u64 {
-    (1 << (height + 1)) - 2
-}
-
-fn left_peak_height_pos(mmr_size: u64) -> (u32, u64) {
-    let mut height = 1;
-    let mut prev_pos = 0;
-    let mut pos = get_peak_pos_by_height(height);
-    while pos < mmr_size {
-        height += 1;
-        prev_pos = pos;
-        pos = get_peak_pos_by_height(height);
-    }
-    (height - 1, prev_pos)
+        let peak_size = highest_peak_size(remaining);
+        pos_offset += peak_size;
+        remaining -= peak_size;
+        // a leaf is always the last peak
+        if peak_size == 1 {
+            remaining = 0;
+        }
+        Some(pos_offset - 1)
+    })
 }
@@ -0,0 +1,152 @@
+//! Key-value store adapter
+//!
+//! `KVMMRStore` implements `MMRStore` over any key-value store, such as RocksDB or sled,
+//! elems are encoded by an `ElemCodec`.
+//!
+//! Keys under the namespace `prefix`, `len` is the length of `prefix`:
+//!
+//! | key                                | value                                  |
+//! |------------------------------------|----------------------------------------|
+//! | `len ++ prefix ++ 0x00 ++ pos`     | elem at `pos`, `pos` is 8 bytes        |
+//! | `len ++ prefix ++ 0x01`            | MMR size, 8 bytes, little endian       |
+//!
+//! `len` is 4 bytes big endian, so keys of different prefixes never collide. Positions are
+//! big endian, so elems are ordered by position in sorted stores.
+//!
+//! Writes are buffered in memory and written in one atomic batch by `MMRStore::commit`,
+//! which is called by `MMR::commit`, so a failed commit leaves the store unchanged.
+
+use crate::codec::CodecError;
+use crate::collections::BTreeMap;
+use crate::vec::Vec;
+use crate::{ElemCodec, MMRStore, Result};
+use core::cmp;
+use core::convert::TryInto;
+use core::marker::PhantomData;
+
+const ELEM_TAG: u8 = 0;
+const SIZE_TAG: u8 = 1;
+
+/// Minimal key-value store
+pub trait KVStore {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>>;
+    /// Put pairs and delete keys paired with `None` atomically
+    fn write_batch(&mut self, batch: Vec<(Vec<u8>, Option<Vec<u8>>)>) -> Result<()>;
+}
+
+pub struct KVMMRStore<T, C, S> {
+    kv: S,
+    // length of prefix and prefix
+    namespace: Vec<u8>,
+    // pending writes of the next commit, `None` deletes the key
+    pending: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+    codec: PhantomData<(T, C)>,
+}
+
+impl<T, C: ElemCodec<Item = T>, S: KVStore> KVMMRStore<T, C, S> {
+    /// Store elems under the namespace `prefix`, an empty prefix is a namespace as well
+    pub fn new(kv: S, prefix: Vec<u8>) -> Self {
+        let mut namespace = Vec::with_capacity(prefix.len() + 4);
+        namespace.extend_from_slice(&(prefix.len() as u32).to_be_bytes());
+        namespace.extend_from_slice(&prefix);
+        KVMMRStore {
+            kv,
+            namespace,
+            pending: BTreeMap::new(),
+            codec: PhantomData,
+        }
+    }
+
+    pub fn kv(&self) -> &S {
+        &self.kv
+    }
+
+    fn elem_key(&self, pos: u64) -> Vec<u8> {
+        let mut key = Vec::with_capacity(self.namespace.len() + 9);
+        key.extend_from_slice(&self.namespace);
+        key.push(ELEM_TAG);
+        key.extend_from_slice(&pos.to_be_bytes());
+        key
+    }
+
+    fn size_key(&self) -> Vec<u8> {
+        let mut key = Vec::with_capacity(self.namespace.len() + 1);
+        key.extend_from_slice(&self.namespace);
+        key.push(SIZE_TAG);
+        key
+    }
+
+    // pending writes shadow the key-value store
+    fn get_value(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        match self.pending.get(key) {
+            Some(value) => Ok(value.clone()),
+            None => self.kv.get(key),
+        }
+    }
+
+    fn put_size(&mut self, mmr_size: u64) {
+        self.pending
+            .insert(self.size_key(), Some(mmr_size.to_le_bytes().to_vec()));
+    }
+
+    /// MMR size written by appends and truncates, including pending ones,
+    /// 0 if nothing is stored
+    pub fn mmr_size(&self) -> Result<u64> {
+        match self.get_value(&self.size_key())? {
+            Some(bytes) => {
+                let bytes = bytes[..].try_into().map_err(|_| CodecError::InvalidElem)?;
+                Ok(u64::from_le_bytes(bytes))
+            }
+            None => Ok(0),
+        }
+    }
+}
+
+impl<T, C: ElemCodec<Item = T>, S: KVStore> MMRStore<T> for KVMMRStore<T, C, S> {
+    fn get_elem(&self, pos: u64) -> Result<Option<T>> {
+        self.get_value(&self.elem_key(pos))?
+            .map(|bytes| C::decode_all(&bytes))
+            .transpose()
+    }
+
+    /// Buffer elems, the size only grows, since appends of a commit may come in any order
+    fn append(&mut self, pos: u64, elems: Vec<T>) -> Result<()> {
+        let mmr_size = cmp::max(self.mmr_size()?, pos + elems.len() as u64);
+        for (i, elem) in elems.iter().enumerate() {
+            let key = self.elem_key(pos + i as u64);
+            self.pending.insert(key, Some(C::encode_to_vec(elem)));
+        }
+        self.put_size(mmr_size);
+        Ok(())
+    }
+
+    fn truncate(&mut self, mmr_size: u64) -> Result<()> {
+        let stored_size = self.mmr_size()?;
+        if mmr_size >= stored_size {
+            return Ok(());
+        }
+        for pos in mmr_size..stored_size {
+            let key = self.elem_key(pos);
+            self.pending.insert(key, None);
+        }
+        self.put_size(mmr_size);
+        Ok(())
+    }
+
+    fn remove(&mut self, pos_list: Vec<u64>) -> Result<()> {
+        for pos in pos_list {
+            let key = self.elem_key(pos);
+            self.pending.insert(key, None);
+        }
+        Ok(())
+    }
+
+    /// Write pending elems and the size in one batch
+    fn commit(&mut self) -> Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let batch = core::mem::take(&mut self.pending).into_iter().collect();
+        self.kv.write_batch(batch)
+    }
+}
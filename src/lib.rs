@@ -1,19 +1,93 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
+#[cfg(feature = "alloc")]
+mod ancestry_proof;
+#[cfg(feature = "async")]
+mod async_mmr;
+#[cfg(feature = "alloc")]
+mod bagging;
+#[cfg(feature = "alloc")]
+mod cached_store;
+#[cfg(feature = "alloc")]
+mod codec;
 mod error;
+#[cfg(feature = "std")]
+mod file_store;
 mod helper;
+#[cfg(feature = "alloc")]
+mod kv_store;
 mod merge;
+#[cfg(feature = "alloc")]
 mod mmr;
+#[cfg(feature = "alloc")]
 mod mmr_store;
+#[cfg(feature = "rayon")]
+mod parallel;
+#[cfg(feature = "alloc")]
+mod peaks_accumulator;
+#[cfg(feature = "alloc")]
+mod prune_list;
+#[cfg(feature = "alloc")]
+mod range_proof;
+#[cfg(feature = "std")]
+mod render;
+#[cfg(feature = "alloc")]
+mod sampling;
 #[cfg(test)]
 mod tests;
+#[cfg(feature = "alloc")]
 pub mod util;
+mod verifier;
+#[cfg(feature = "alloc")]
+mod weighted;
 
+#[cfg(feature = "alloc")]
+pub use ancestry_proof::AncestryProof;
+#[cfg(feature = "async")]
+pub use async_mmr::{AsyncMMR, AsyncMMRStore};
+#[cfg(feature = "alloc")]
+pub use bagging::{
+    CommitMMRSize, LeftToRightBagging, MMRSizeBagging, PeakBagging, PeakListBagging,
+    RightToLeftBagging,
+};
+#[cfg(feature = "alloc")]
+pub use cached_store::CachedStore;
+#[cfg(feature = "alloc")]
+pub use codec::{
+    BytesCodec, CodecError, ElemCodec, FixedBytesCodec, FixedSizeElemCodec,
+    MERKLE_PROOF_ENCODING_VERSION,
+};
 pub use error::{Error, Result};
-pub use helper::{leaf_index_to_mmr_size, leaf_index_to_pos};
-pub use merge::Merge;
+#[cfg(feature = "std")]
+pub use file_store::FileStore;
+pub use helper::{
+    leaf_count_to_mmr_size, leaf_index_to_mmr_size, leaf_index_to_pos, mmr_size_to_leaf_count,
+    pos_to_leaf_index, MAX_LEAF_COUNT,
+};
+#[cfg(feature = "alloc")]
+pub use kv_store::{KVMMRStore, KVStore};
+pub use merge::{Merge, NodeMerge, TryMerge};
+#[cfg(feature = "alloc")]
 pub use mmr::{MerkleProof, MMR};
+#[cfg(feature = "alloc")]
 pub use mmr_store::MMRStore;
+#[cfg(feature = "rayon")]
+pub use parallel::build_parallel;
+#[cfg(feature = "alloc")]
+pub use peaks_accumulator::PeaksAccumulator;
+#[cfg(feature = "alloc")]
+pub use prune_list::PruneList;
+#[cfg(feature = "alloc")]
+pub use range_proof::RangeProof;
+#[cfg(feature = "std")]
+pub use render::{Highlight, MMRRender};
+#[cfg(feature = "std")]
+pub use sampling::FlyClientDistribution;
+#[cfg(feature = "alloc")]
+pub use sampling::{RandomOracle, SampleDistribution, SampleProof, UniformDistribution};
+pub use verifier::{calculate_root_without_alloc, verify_without_alloc};
+#[cfg(feature = "alloc")]
+pub use weighted::{WeightProof, Weighted};
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "std")] {
@@ -21,7 +95,7 @@ cfg_if::cfg_if! {
         use std::collections;
         use std::vec;
         use std::string;
-    } else {
+    } else if #[cfg(feature = "alloc")] {
         extern crate alloc;
         use alloc::borrow;
         use alloc::collections;
@@ -1,4 +1,65 @@
+use crate::Result;
+
 pub trait Merge {
     type Item;
     fn merge(left: &Self::Item, right: &Self::Item) -> Self::Item;
 }
+
+/// Merge which may fail, such as hashers backed by a hardware module or FFI,
+/// or merges which check invariants of elems.
+/// Return `Error::MergeError` to report the failure.
+///
+/// Every `Merge` is a `TryMerge` which never fails.
+pub trait TryMerge {
+    type Item;
+    fn try_merge(left: &Self::Item, right: &Self::Item) -> Result<Self::Item>;
+}
+
+impl<M: Merge> TryMerge for M {
+    type Item = M::Item;
+    fn try_merge(left: &Self::Item, right: &Self::Item) -> Result<Self::Item> {
+        Ok(M::merge(left, right))
+    }
+}
+
+/// Merge which commits where a node sits in the tree.
+///
+/// Leaves are hashed by `hash_leaf` before stored and again when proofs of them are verified,
+/// so both push and verify take the raw elems. Internal nodes are merged with
+/// their position and height, so an internal node can't be passed off as a leaf, e.g.
+/// `hash_leaf(leaf) = H(0x00 || leaf)` and `merge_node = H(0x01 || pos || height || left || right)`
+/// in the style of RFC 6962 domain separation.
+///
+/// Every `TryMerge` is a `NodeMerge` which keeps leaves as they are and ignores positions.
+pub trait NodeMerge {
+    type Item;
+    /// Hash a leaf before it is pushed into MMR or verified by a proof
+    fn hash_leaf(leaf: Self::Item) -> Result<Self::Item>;
+    /// Merge children into the parent node at `parent_pos`, `height` is the height of parent
+    fn merge_node(
+        parent_pos: u64,
+        height: u32,
+        left: &Self::Item,
+        right: &Self::Item,
+    ) -> Result<Self::Item>;
+    /// Merge peaks while bagging, peaks are not nodes of the tree
+    fn merge_peaks(left: &Self::Item, right: &Self::Item) -> Result<Self::Item>;
+}
+
+impl<M: TryMerge> NodeMerge for M {
+    type Item = M::Item;
+    fn hash_leaf(leaf: Self::Item) -> Result<Self::Item> {
+        Ok(leaf)
+    }
+    fn merge_node(
+        _parent_pos: u64,
+        _height: u32,
+        left: &Self::Item,
+        right: &Self::Item,
+    ) -> Result<Self::Item> {
+        M::try_merge(left, right)
+    }
+    fn merge_peaks(left: &Self::Item, right: &Self::Item) -> Result<Self::Item> {
+        M::try_merge(left, right)
+    }
+}
@@ -5,27 +5,49 @@
 //! https://github.com/mimblewimble/grin/blob/0ff6763ee64e5a14e70ddd4642b99789a1648a32/core/src/core/pmmr.rs#L606
 
 use crate::borrow::Cow;
-use crate::collections::VecDeque;
-use crate::helper::{get_peaks, parent_offset, pos_height_in_tree, sibling_offset};
+use crate::collections::{BTreeMap, VecDeque};
+use crate::helper::{
+    get_peaks, is_valid_mmr_size, leaf_index_to_pos, mmr_size_to_leaf_count, parent_offset,
+    pos_height_in_tree, pos_to_leaf_index, sibling_offset,
+};
 use crate::mmr_store::{MMRBatch, MMRStore};
+use crate::range_proof::range_proof_positions;
+use crate::sampling::sample_weights;
 use crate::vec;
 use crate::vec::Vec;
-use crate::{Error, Merge, Result};
+use crate::{
+    AncestryProof, Error, NodeMerge, PeakBagging, PruneList, RandomOracle, RangeProof, Result,
+    RightToLeftBagging, SampleDistribution, SampleProof, WeightProof, Weighted,
+};
 use core::fmt::Debug;
 use core::marker::PhantomData;
 
-pub struct MMR<T, M, S: MMRStore<T>> {
+// nodes read from store in one batch while validating
+const VALIDATE_BATCH_SIZE: u64 = 1024;
+
+pub struct MMR<T, M, S: MMRStore<T>, B = RightToLeftBagging> {
     mmr_size: u64,
     batch: MMRBatch<T, S>,
+    prune_list: PruneList,
     merge: PhantomData<M>,
+    bagging: PhantomData<B>,
 }
 
-impl<'a, T: Clone + PartialEq + Debug, M: Merge<Item = T>, S: MMRStore<T>> MMR<T, M, S> {
+impl<T: Clone + PartialEq + Debug, M: NodeMerge<Item = T>, S: MMRStore<T>, B: PeakBagging<T>>
+    MMR<T, M, S, B>
+{
     pub fn new(mmr_size: u64, store: S) -> Self {
+        Self::with_prune_list(mmr_size, store, PruneList::new())
+    }
+
+    /// create a MMR whose store is pruned by `prune_list`
+    pub fn with_prune_list(mmr_size: u64, store: S, prune_list: PruneList) -> Self {
         MMR {
             mmr_size,
             batch: MMRBatch::new(store),
+            prune_list,
             merge: PhantomData,
+            bagging: PhantomData,
         }
     }
 
@@ -47,12 +69,16 @@ impl<'a, T: Clone + PartialEq + Debug, M: Merge<Item = T>, S: MMRStore<T>> MMR<T
         self.mmr_size == 0
     }
 
-    // push a element and return position
+    pub fn prune_list(&self) -> &PruneList {
+        &self.prune_list
+    }
+
+    // push a element and return position, the element is hashed by `M::hash_leaf` before stored
     pub fn push(&mut self, elem: T) -> Result<u64> {
         let mut elems: Vec<T> = Vec::new();
         // position of new elem
         let elem_pos = self.mmr_size;
-        elems.push(elem);
+        elems.push(M::hash_leaf(elem)?);
         let mut height = 0u32;
         let mut pos = elem_pos;
         // continue to merge tree node if next pos heigher than current
@@ -62,7 +88,7 @@ impl<'a, T: Clone + PartialEq + Debug, M: Merge<Item = T>, S: MMRStore<T>> MMR<T
             let right_pos = left_pos + sibling_offset(height);
             let left_elem = self.find_elem(left_pos, &elems)?;
             let right_elem = self.find_elem(right_pos, &elems)?;
-            let parent_elem = M::merge(&left_elem, &right_elem);
+            let parent_elem = M::merge_node(pos, height + 1, &left_elem, &right_elem)?;
             elems.push(parent_elem);
             height += 1
         }
@@ -73,156 +99,346 @@ impl<'a, T: Clone + PartialEq + Debug, M: Merge<Item = T>, S: MMRStore<T>> MMR<T
         Ok(elem_pos)
     }
 
+    /// push elems and return their positions,
+    /// new nodes are calculated in one pass and appended to the batch at once
+    pub fn push_batch<I: IntoIterator<Item = T>>(&mut self, elems: I) -> Result<Vec<u64>> {
+        let mut new_elems: Vec<T> = Vec::new();
+        let mut positions: Vec<u64> = Vec::new();
+        // position of next elem
+        let mut pos = self.mmr_size;
+        for elem in elems {
+            positions.push(pos);
+            new_elems.push(M::hash_leaf(elem)?);
+            let mut height = 0u32;
+            // continue to merge tree node if next pos heigher than current
+            while pos_height_in_tree(pos + 1) > height {
+                pos += 1;
+                let left_pos = pos - parent_offset(height);
+                let right_pos = left_pos + sibling_offset(height);
+                let parent_elem = {
+                    let left_elem = self.find_elem(left_pos, &new_elems)?;
+                    let right_elem = self.find_elem(right_pos, &new_elems)?;
+                    M::merge_node(pos, height + 1, &left_elem, &right_elem)?
+                };
+                new_elems.push(parent_elem);
+                height += 1
+            }
+            pos += 1;
+        }
+        if !new_elems.is_empty() {
+            // store hashes
+            self.batch.append(self.mmr_size, new_elems);
+            // update mmr_size
+            self.mmr_size = pos;
+        }
+        Ok(positions)
+    }
+
+    /// rewind MMR to a previous `mmr_size`, nodes at or above it are dropped.
+    /// the removal is written to store on commit
+    pub fn rewind(&mut self, mmr_size: u64) -> Result<()> {
+        if mmr_size > self.mmr_size || !is_valid_mmr_size(mmr_size) {
+            return Err(Error::InvalidMMRSize);
+        }
+        self.prune_list.truncate(mmr_size)?;
+        self.batch.truncate(mmr_size);
+        self.mmr_size = mmr_size;
+        Ok(())
+    }
+
+    /// prune subtrees of positions, nodes under them are removed from store on commit.
+    /// subtree roots are kept, so the MMR can still push elems and generate proofs
+    /// for leaves which are not pruned
+    pub fn prune(&mut self, pos_list: Vec<u64>) -> Result<()> {
+        if pos_list.iter().any(|&pos| pos >= self.mmr_size) {
+            return Err(Error::PruneInvalidPosition);
+        }
+        for pos in pos_list {
+            let removed = self.prune_list.add(pos, self.mmr_size);
+            self.batch.remove(removed);
+        }
+        Ok(())
+    }
+
     /// get_root
-    pub fn get_root(&self) -> Result<T> {
+    pub fn get_root(&self) -> Result<B::Root> {
         if self.mmr_size == 0 {
             return Err(Error::GetRootOnEmpty);
-        } else if self.mmr_size == 1 {
-            return self.batch.get_elem(0)?.ok_or(Error::InconsistentStore);
         }
-        let peaks: Vec<T> = get_peaks(self.mmr_size)
+        let peaks: Vec<T> = self
+            .batch
+            .get_elems(&get_peaks(self.mmr_size))?
             .into_iter()
-            .map(|peak_pos| {
-                self.batch
-                    .get_elem(peak_pos)
-                    .and_then(|elem| elem.ok_or(Error::InconsistentStore))
-            })
+            .map(|elem| elem.ok_or(Error::InconsistentStore))
             .collect::<Result<Vec<T>>>()?;
-        self.bag_rhs_peaks(peaks)?.ok_or(Error::InconsistentStore)
+        B::bag_peaks::<M>(peaks, self.mmr_size)
     }
 
-    fn bag_rhs_peaks(&self, mut rhs_peaks: Vec<T>) -> Result<Option<T>> {
-        while rhs_peaks.len() > 1 {
-            let right_peak = rhs_peaks.pop().expect("pop");
-            let left_peak = rhs_peaks.pop().expect("pop");
-            rhs_peaks.push(M::merge(&right_peak, &left_peak));
+    /// Generate merkle proof for positions, a position can be a leaf or an internal node
+    /// 1. calculate positions of proof items by peak from left to right
+    /// 2. fetch proof items from store in one batch
+    /// 3. right hand side peaks may be bagged into one root by bagging strategy
+    pub fn gen_proof(&self, pos_list: Vec<u64>) -> Result<MerkleProof<T, M, B>> {
+        if pos_list.iter().any(|&pos| self.prune_list.is_pruned(pos)) {
+            return Err(Error::GenProofForPrunedLeaves);
         }
-        Ok(rhs_peaks.pop())
+        let (proof_positions, bagging_track) = gen_proof_positions(self.mmr_size, pos_list)?;
+        // fetch all proof items in one batch
+        let mut proof = self
+            .batch
+            .get_elems(&proof_positions)?
+            .into_iter()
+            .map(|elem| elem.ok_or(Error::InconsistentStore))
+            .collect::<Result<Vec<T>>>()?;
+
+        if bagging_track > 1 {
+            let rhs_peaks = proof.split_off(proof.len() - bagging_track);
+            proof.extend(B::bag_rhs_peaks::<M>(rhs_peaks)?);
+        }
+
+        Ok(MerkleProof::new(self.mmr_size, proof))
     }
 
-    /// generate merkle proof for a peak
-    /// the pos_list must be sorted, otherwise the behaviour is undefined
-    ///
-    /// 1. find a lower tree in peak that can generate a complete merkle proof for position
-    /// 2. find that tree by compare positions
-    /// 3. generate proof for each positions
-    fn gen_proof_for_peak(
+    /// Generate merkle proof for leaf indices
+    pub fn gen_proof_by_leaf_indices(
         &self,
-        proof: &mut Vec<T>,
-        pos_list: Vec<u64>,
-        peak_pos: u64,
-    ) -> Result<()> {
-        // do nothing if position itself is the peak
-        if pos_list.len() == 1 && pos_list == [peak_pos] {
-            return Ok(());
+        leaf_indices: Vec<u64>,
+    ) -> Result<MerkleProof<T, M, B>> {
+        let leaf_count = mmr_size_to_leaf_count(self.mmr_size)?;
+        if leaf_indices
+            .iter()
+            .any(|&leaf_index| leaf_index >= leaf_count)
+        {
+            return Err(Error::GenProofForInvalidLeaves);
         }
-        // take peak root from store if no positions need to be proof
-        if pos_list.is_empty() {
-            proof.push(
-                self.batch
-                    .get_elem(peak_pos)?
-                    .ok_or(Error::InconsistentStore)?,
-            );
-            return Ok(());
+        self.gen_proof(leaf_indices.into_iter().map(leaf_index_to_pos).collect())
+    }
+
+    /// Generate proof for the contiguous leaves of indices in `start_leaf..end_leaf`,
+    /// the proof only contains boundary siblings and peaks out of the range
+    pub fn gen_range_proof(&self, start_leaf: u64, end_leaf: u64) -> Result<RangeProof<T, M, B>> {
+        let (proof_positions, bagging_track) =
+            range_proof_positions(self.mmr_size, start_leaf, end_leaf)?;
+        // boundary siblings are removed if the range is partially pruned
+        if proof_positions
+            .iter()
+            .any(|&pos| self.prune_list.is_removed(pos))
+        {
+            return Err(Error::GenProofForPrunedLeaves);
         }
+        let mut proof = self
+            .batch
+            .get_elems(&proof_positions)?
+            .into_iter()
+            .map(|elem| elem.ok_or(Error::InconsistentStore))
+            .collect::<Result<Vec<T>>>()?;
 
-        let mut queue: VecDeque<_> = pos_list.into_iter().map(|pos| (pos, 0u32)).collect();
-        // Generate sub-tree merkle proof for positions
-        while let Some((pos, height)) = queue.pop_front() {
-            debug_assert!(pos <= peak_pos);
-            if pos == peak_pos {
-                break;
-            }
+        if bagging_track > 1 {
+            let rhs_peaks = proof.split_off(proof.len() - bagging_track);
+            proof.extend(B::bag_rhs_peaks::<M>(rhs_peaks)?);
+        }
 
-            // calculate sibling
-            let (sib_pos, parent_pos) = {
-                let next_height = pos_height_in_tree(pos + 1);
-                let sibling_offset = sibling_offset(height);
-                if next_height > height {
-                    // implies pos is right sibling
-                    (pos - sibling_offset, pos + 1)
-                } else {
-                    // pos is left sibling
-                    (pos + sibling_offset, pos + parent_offset(height))
-                }
-            };
+        Ok(RangeProof::new(self.mmr_size, proof))
+    }
 
-            if Some(&sib_pos) == queue.front().map(|(pos, _)| pos) {
-                // drop sibling
-                queue.pop_front();
-            } else {
-                proof.push(
-                    self.batch
-                        .get_elem(sib_pos)?
-                        .ok_or(Error::InconsistentStore)?,
-                );
-            }
-            if parent_pos < peak_pos {
-                // save pos to tree buf
-                queue.push_back((parent_pos, height + 1));
-            }
+    /// Generate proof that the MMR of `prev_mmr_size` is a prefix of this MMR,
+    /// the proof contains peaks of the previous MMR and a merkle proof of them
+    pub fn gen_ancestry_proof(&self, prev_mmr_size: u64) -> Result<AncestryProof<T, M, B>> {
+        if prev_mmr_size == 0 || prev_mmr_size > self.mmr_size || !is_valid_mmr_size(prev_mmr_size)
+        {
+            return Err(Error::InvalidMMRSize);
         }
-        Ok(())
+        let peaks_pos = get_peaks(prev_mmr_size);
+        let prev_peaks_proof = self.gen_proof(peaks_pos.clone())?;
+        let prev_peaks = peaks_pos
+            .iter()
+            .map(|&peak_pos| {
+                self.batch
+                    .get_elem(peak_pos)
+                    .and_then(|elem| elem.ok_or(Error::InconsistentStore))
+            })
+            .collect::<Result<Vec<T>>>()?;
+        Ok(AncestryProof::new(
+            prev_mmr_size,
+            prev_peaks,
+            prev_peaks_proof,
+        ))
     }
 
-    /// Generate merkle proof for positions
-    /// 1. sort positions
-    /// 2. push merkle proof to proof by peak from left to right
-    /// 3. push bagged right hand side root
-    pub fn gen_proof(&self, mut pos_list: Vec<u64>) -> Result<MerkleProof<T, M>> {
-        if pos_list.is_empty() {
-            return Err(Error::GenProofForInvalidLeaves);
+    /// Check every node of the MMR against its store, see `validate_since`
+    pub fn validate(&self) -> Result<()> {
+        self.validate_since(0)
+    }
+
+    /// Check nodes added since the MMR of `prev_mmr_size`, nodes before it are trusted.
+    /// Returns the first bad position by `Error::MissingNode` or `Error::InvalidNode`
+    /// 1. every node which is not removed by pruning must be present
+    /// 2. every internal node must equal the merge of its children,
+    ///    leaves and pruned roots whose children are removed can't be checked
+    pub fn validate_since(&self, prev_mmr_size: u64) -> Result<()> {
+        if prev_mmr_size > self.mmr_size || !is_valid_mmr_size(prev_mmr_size) {
+            return Err(Error::InvalidMMRSize);
         }
-        if self.mmr_size == 1 && pos_list == [0] {
-            return Ok(MerkleProof::new(self.mmr_size, Vec::new()));
-        }
-        // ensure positions is sorted
-        pos_list.sort_unstable();
-        let peaks = get_peaks(self.mmr_size);
-        let mut proof: Vec<T> = Vec::new();
-        // generate merkle proof for each peaks
-        let mut bagging_track = 0;
-        for peak_pos in peaks {
-            let pos_list: Vec<_> = take_while_vec(&mut pos_list, |&pos| pos <= peak_pos);
-            if pos_list.is_empty() {
-                bagging_track += 1;
-            } else {
-                bagging_track = 0;
+        let mut start_pos = prev_mmr_size;
+        while start_pos < self.mmr_size {
+            let end_pos = self.mmr_size.min(start_pos + VALIDATE_BATCH_SIZE);
+            // nodes of this batch and their children before it
+            let mut pos_list: Vec<u64> = (start_pos..end_pos).collect();
+            for pos in start_pos..end_pos {
+                let height = pos_height_in_tree(pos);
+                if height > 0 {
+                    pos_list.push(pos - parent_offset(height - 1));
+                    pos_list.push(pos - 1);
+                }
             }
-            self.gen_proof_for_peak(&mut proof, pos_list, peak_pos)?;
+            pos_list.sort_unstable();
+            pos_list.dedup();
+            pos_list.retain(|&pos| !self.prune_list.is_removed(pos));
+            let elems: BTreeMap<u64, T> = pos_list
+                .iter()
+                .cloned()
+                .zip(self.batch.get_elems(&pos_list)?)
+                .filter_map(|(pos, elem)| elem.map(|elem| (pos, elem)))
+                .collect();
+            let get_elem = |pos: u64| elems.get(&pos).ok_or(Error::MissingNode(pos));
+            for pos in start_pos..end_pos {
+                if self.prune_list.is_removed(pos) {
+                    continue;
+                }
+                let elem = get_elem(pos)?;
+                let height = pos_height_in_tree(pos);
+                if height == 0 {
+                    continue;
+                }
+                let left_pos = pos - parent_offset(height - 1);
+                // children of a pruned root are removed
+                if self.prune_list.is_removed(left_pos) {
+                    continue;
+                }
+                let left_elem = get_elem(left_pos)?;
+                let right_elem = get_elem(pos - 1)?;
+                if M::merge_node(pos, height, left_elem, right_elem)? != *elem {
+                    return Err(Error::InvalidNode(pos));
+                }
+            }
+            start_pos = end_pos;
         }
+        Ok(())
+    }
 
-        // ensure no remain positions
-        if !pos_list.is_empty() {
-            return Err(Error::GenProofForInvalidLeaves);
+    pub fn commit(self) -> Result<()> {
+        self.batch.commit()
+    }
+}
+
+impl<
+        T: Clone + PartialEq + Debug + Weighted,
+        M: NodeMerge<Item = T>,
+        S: MMRStore<T>,
+        B: PeakBagging<T>,
+    > MMR<T, M, S, B>
+{
+    fn get_existing_elem(&self, pos: u64) -> Result<T> {
+        if self.prune_list.is_removed(pos) {
+            return Err(Error::GenProofForPrunedLeaves);
         }
+        self.batch.get_elem(pos)?.ok_or(Error::InconsistentStore)
+    }
 
-        if bagging_track > 1 {
-            let rhs_peaks = proof.split_off(proof.len() - bagging_track);
-            proof.push(self.bag_rhs_peaks(rhs_peaks)?.expect("bagging rhs peaks"));
+    /// Find the leaf whose cumulative weight interval contains `weight`,
+    /// return the proof of the leaf. Leaves of zero weight are never found.
+    pub fn find_leaf_by_weight(&self, weight: T::Weight) -> Result<WeightProof<T, M, B>> {
+        let leaf_index = self.find_leaf_index_by_weight(weight)?;
+        self.gen_left_weight_proof(leaf_index)
+    }
+
+    /// 1. find the peak from left to right
+    /// 2. descend from the peak, go right and add the weight of left child
+    ///    if `weight` is beyond the left child
+    fn find_leaf_index_by_weight(&self, weight: T::Weight) -> Result<u64> {
+        if self.mmr_size == 0 {
+            return Err(Error::GetRootOnEmpty);
+        }
+        let peaks_pos = get_peaks(self.mmr_size);
+        let peaks = self.batch.get_elems(&peaks_pos)?;
+        let mut left_weight = T::Weight::default();
+        for (peak_pos, peak) in peaks_pos.into_iter().zip(peaks) {
+            let peak = peak.ok_or(Error::InconsistentStore)?;
+            if weight >= left_weight + peak.weight() {
+                left_weight = left_weight + peak.weight();
+                continue;
+            }
+            let mut pos = peak_pos;
+            for height in (0..pos_height_in_tree(peak_pos)).rev() {
+                let left_pos = pos - parent_offset(height);
+                let left_child_weight = self.get_existing_elem(left_pos)?.weight();
+                if weight < left_weight + left_child_weight {
+                    pos = left_pos;
+                } else {
+                    left_weight = left_weight + left_child_weight;
+                    pos -= 1;
+                }
+            }
+            return pos_to_leaf_index(pos);
         }
+        Err(Error::WeightOutOfRange)
+    }
 
-        Ok(MerkleProof::new(self.mmr_size, proof))
+    /// Generate proof of the leaf at `leaf_index` and the cumulative weight on the left of it
+    pub fn gen_left_weight_proof(&self, leaf_index: u64) -> Result<WeightProof<T, M, B>> {
+        let proof = self.gen_proof_by_leaf_indices(vec![leaf_index])?;
+        Ok(WeightProof::new(leaf_index, proof))
     }
 
-    pub fn commit(self) -> Result<()> {
-        self.batch.commit()
+    /// Sample `sample_count` leaves by weight targets derived from the root, FlyClient style,
+    /// and generate one proof of them
+    /// 1. derive pseudo-random numbers from the root by `O`, which makes it non-interactive
+    /// 2. map them to weight targets in the total weight by `distribution`
+    /// 3. find leaves of the targets, a leaf may be sampled more than once
+    pub fn gen_sample_proof<O: RandomOracle<B::Root>, D: SampleDistribution<T::Weight>>(
+        &self,
+        distribution: &D,
+        sample_count: u32,
+    ) -> Result<SampleProof<T, M, B>>
+    where
+        B::Root: Weighted<Weight = T::Weight>,
+    {
+        let root = self.get_root()?;
+        let mut leaf_indices = sample_weights::<_, O, D>(&root, distribution, sample_count)
+            .into_iter()
+            .map(|weight| self.find_leaf_index_by_weight(weight))
+            .collect::<Result<Vec<u64>>>()?;
+        leaf_indices.sort_unstable();
+        leaf_indices.dedup();
+        let proof = self.gen_proof(
+            leaf_indices
+                .iter()
+                .cloned()
+                .map(leaf_index_to_pos)
+                .collect(),
+        )?;
+        Ok(SampleProof::new(leaf_indices, proof))
     }
 }
 
 #[derive(Debug)]
-pub struct MerkleProof<T, M> {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MerkleProof<T, M, B = RightToLeftBagging> {
     mmr_size: u64,
     proof: Vec<T>,
+    #[cfg_attr(feature = "serde", serde(skip))]
     merge: PhantomData<M>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    bagging: PhantomData<B>,
 }
 
-impl<T: PartialEq + Debug + Clone, M: Merge<Item = T>> MerkleProof<T, M> {
+impl<T: PartialEq + Debug + Clone, M: NodeMerge<Item = T>, B: PeakBagging<T>> MerkleProof<T, M, B> {
     pub fn new(mmr_size: u64, proof: Vec<T>) -> Self {
         MerkleProof {
             mmr_size,
             proof,
             merge: PhantomData,
+            bagging: PhantomData,
         }
     }
 
@@ -234,8 +450,16 @@ impl<T: PartialEq + Debug + Clone, M: Merge<Item = T>> MerkleProof<T, M> {
         &self.proof
     }
 
-    pub fn calculate_root(&self, leaves: Vec<(u64, T)>) -> Result<T> {
-        calculate_root::<_, M, _>(leaves, self.mmr_size, self.proof.iter())
+    /// calculate root from leaves given by `(pos, leaf)`, leaves are the elems pushed into MMR,
+    /// they are hashed by `M::hash_leaf` as in push
+    pub fn calculate_root(&self, leaves: Vec<(u64, T)>) -> Result<B::Root> {
+        self.calculate_root_from_nodes(hash_leaves::<_, M>(leaves)?)
+    }
+
+    /// calculate root from nodes given by `(pos, node)`, nodes are taken as they are stored
+    /// in MMR, that is, a leaf node is the elem hashed by `M::hash_leaf` already
+    pub fn calculate_root_from_nodes(&self, nodes: Vec<(u64, T)>) -> Result<B::Root> {
+        calculate_root::<_, M, B, _>(nodes, self.mmr_size, self.proof.iter())
     }
 
     /// from merkle proof of leaf n to calculate merkle root of n + 1 leaves.
@@ -244,11 +468,13 @@ impl<T: PartialEq + Debug + Clone, M: Merge<Item = T>> MerkleProof<T, M> {
     /// this is kinda tricky, but it works, and useful
     pub fn calculate_root_with_new_leaf(
         &self,
-        mut leaves: Vec<(u64, T)>,
+        leaves: Vec<(u64, T)>,
         new_pos: u64,
         new_elem: T,
         new_mmr_size: u64,
-    ) -> Result<T> {
+    ) -> Result<B::Root> {
+        let mut leaves = hash_leaves::<_, M>(leaves)?;
+        let new_elem = M::hash_leaf(new_elem)?;
         let pos_height = pos_height_in_tree(new_pos);
         let next_height = pos_height_in_tree(new_pos + 1);
         if next_height > pos_height {
@@ -261,23 +487,139 @@ impl<T: PartialEq + Debug + Clone, M: Merge<Item = T>> MerkleProof<T, M> {
                 i += 1
             }
             peaks_hashes[i..].reverse();
-            calculate_root::<_, M, _>(vec![(new_pos, new_elem)], new_mmr_size, peaks_hashes.iter())
+            calculate_root::<_, M, B, _>(
+                vec![(new_pos, new_elem)],
+                new_mmr_size,
+                peaks_hashes.iter(),
+            )
         } else {
             leaves.push((new_pos, new_elem));
-            calculate_root::<_, M, _>(leaves, new_mmr_size, self.proof.iter())
+            calculate_root::<_, M, B, _>(leaves, new_mmr_size, self.proof.iter())
         }
     }
 
-    pub fn verify(&self, root: T, leaves: Vec<(u64, T)>) -> Result<bool> {
+    /// verify leaves given by `(pos, leaf)`, see `calculate_root`
+    pub fn verify(&self, root: B::Root, leaves: Vec<(u64, T)>) -> Result<bool>
+    where
+        B::Root: PartialEq,
+    {
         self.calculate_root(leaves)
             .map(|calculated_root| calculated_root == root)
     }
+
+    /// verify nodes given by `(pos, node)`, see `calculate_root_from_nodes`
+    pub fn verify_nodes(&self, root: B::Root, nodes: Vec<(u64, T)>) -> Result<bool>
+    where
+        B::Root: PartialEq,
+    {
+        self.calculate_root_from_nodes(nodes)
+            .map(|calculated_root| calculated_root == root)
+    }
+
+    /// verify leaves given by `(leaf_index, leaf)`
+    pub fn verify_by_leaf_indices(&self, root: B::Root, leaves: Vec<(u64, T)>) -> Result<bool>
+    where
+        B::Root: PartialEq,
+    {
+        let leaf_count = mmr_size_to_leaf_count(self.mmr_size)?;
+        if leaves
+            .iter()
+            .any(|(leaf_index, _)| *leaf_index >= leaf_count)
+        {
+            return Err(Error::CorruptedProof);
+        }
+        let leaves = leaves
+            .into_iter()
+            .map(|(leaf_index, leaf)| (leaf_index_to_pos(leaf_index), leaf))
+            .collect();
+        self.verify(root, leaves)
+    }
+
+    /// Update the proof of `leaves` after `new_leaves` are appended to the MMR,
+    /// returns a proof of the same leaves for the new MMR.
+    ///
+    /// `leaves` are the elems pushed into MMR, as in `calculate_root`. `peaks` are peaks of
+    /// the MMR of this proof ordered from left to right, such as peaks kept by
+    /// `PeaksAccumulator`. They are needed because right hand side peaks may be
+    /// bagged into one proof item, while new leaves are merged with them one by one.
+    pub fn update_with_new_leaves(
+        &self,
+        leaves: Vec<(u64, T)>,
+        peaks: Vec<T>,
+        new_leaves: Vec<T>,
+    ) -> Result<Self>
+    where
+        B::Root: PartialEq,
+    {
+        let peaks_pos = get_peaks(self.mmr_size);
+        if peaks.len() != peaks_pos.len() {
+            return Err(Error::CorruptedProof);
+        }
+        let leaves = hash_leaves::<_, M>(leaves)?;
+        // peaks must be the peaks of the MMR which is proved by this proof
+        if self.calculate_root_from_nodes(leaves.clone())?
+            != B::bag_peaks::<M>(peaks.clone(), self.mmr_size)?
+        {
+            return Err(Error::CorruptedProof);
+        }
+        let pos_list: Vec<u64> = leaves.iter().map(|(pos, _)| *pos).collect();
+        let (proof_positions, bagging_track) =
+            gen_proof_positions(self.mmr_size, pos_list.clone())?;
+        // proof items before the bagged right hand side peaks are nodes at `proof_positions`
+        let known_count = if bagging_track > 1 {
+            proof_positions.len() - bagging_track
+        } else {
+            proof_positions.len()
+        };
+        // rebuild the known part of the MMR, which is enough to push and generate proof
+        let nodes: BTreeMap<u64, T> = proof_positions
+            .into_iter()
+            .zip(self.proof.iter().cloned())
+            .take(known_count)
+            .chain(leaves)
+            .chain(peaks_pos.into_iter().zip(peaks))
+            .collect();
+        let mut mmr = MMR::<T, M, _, B>::new(self.mmr_size, KnownNodes(nodes));
+        // new leaves are hashed by `M::hash_leaf` in push
+        mmr.push_batch(new_leaves)?;
+        mmr.gen_proof(pos_list)
+    }
+}
+
+// nodes known by a proof, new nodes are kept in the batch of MMR and never appended
+struct KnownNodes<T>(BTreeMap<u64, T>);
+
+impl<T: Clone> MMRStore<T> for KnownNodes<T> {
+    fn get_elem(&self, pos: u64) -> Result<Option<T>> {
+        Ok(self.0.get(&pos).cloned())
+    }
+
+    fn append(&mut self, pos: u64, elems: Vec<T>) -> Result<()> {
+        for (i, elem) in elems.into_iter().enumerate() {
+            self.0.insert(pos + i as u64, elem);
+        }
+        Ok(())
+    }
+}
+
+// hash leaves by `M::hash_leaf`, nodes above leaves are taken as they are
+fn hash_leaves<T, M: NodeMerge<Item = T>>(leaves: Vec<(u64, T)>) -> Result<Vec<(u64, T)>> {
+    leaves
+        .into_iter()
+        .map(|(pos, item)| {
+            if pos_height_in_tree(pos) == 0 {
+                Ok((pos, M::hash_leaf(item)?))
+            } else {
+                Ok((pos, item))
+            }
+        })
+        .collect()
 }
 
 fn calculate_peak_root<
     'a,
     T: 'a + PartialEq + Debug + Clone,
-    M: Merge<Item = T>,
+    M: NodeMerge<Item = T>,
     I: Iterator<Item = &'a T>,
 >(
     leaves: Vec<(u64, T)>,
@@ -286,10 +628,12 @@ fn calculate_peak_root<
 ) -> Result<T> {
     debug_assert!(!leaves.is_empty(), "can't be empty");
     // (position, hash, height)
-    let mut queue: VecDeque<_> = leaves
+    let mut queue: Vec<_> = leaves
         .into_iter()
-        .map(|(pos, item)| (pos, item, 0u32))
+        .map(|(pos, item)| (pos, item, pos_height_in_tree(pos)))
         .collect();
+    queue.sort_by_key(|(pos, _, height)| (*height, *pos));
+    let mut queue: VecDeque<_> = queue.into();
 
     // calculate tree root from each items
     while let Some((pos, item, height)) = queue.pop_front() {
@@ -316,13 +660,17 @@ fn calculate_peak_root<
         };
 
         let parent_item = if next_height > height {
-            M::merge(&sibling_item, &item)
+            M::merge_node(parent_pos, height + 1, &sibling_item, &item)?
         } else {
-            M::merge(&item, &sibling_item)
+            M::merge_node(parent_pos, height + 1, &item, &sibling_item)?
         };
 
         if parent_pos < peak_pos {
-            queue.push_back((parent_pos, parent_item, height + 1));
+            push_by_height(
+                &mut queue,
+                (parent_pos, parent_item, height + 1),
+                |(pos, _, height)| (*height, *pos),
+            );
         } else {
             return Ok(parent_item);
         }
@@ -333,7 +681,7 @@ fn calculate_peak_root<
 fn calculate_peaks_hashes<
     'a,
     T: 'a + PartialEq + Debug + Clone,
-    M: Merge<Item = T>,
+    M: NodeMerge<Item = T>,
     I: Iterator<Item = &'a T>,
 >(
     mut leaves: Vec<(u64, T)>,
@@ -385,19 +733,6 @@ fn calculate_peaks_hashes<
     Ok(peaks_hashes)
 }
 
-fn bagging_peaks_hashes<'a, T: 'a + PartialEq + Debug + Clone, M: Merge<Item = T>>(
-    mut peaks_hashes: Vec<T>,
-) -> Result<T> {
-    // bagging peaks
-    // bagging from right to left via hash(right, left).
-    while peaks_hashes.len() > 1 {
-        let right_peak = peaks_hashes.pop().expect("pop");
-        let left_peak = peaks_hashes.pop().expect("pop");
-        peaks_hashes.push(M::merge(&right_peak, &left_peak));
-    }
-    peaks_hashes.pop().ok_or(Error::CorruptedProof)
-}
-
 /// merkle proof
 /// 1. sort items by position
 /// 2. calculate root of each peak
@@ -405,15 +740,100 @@ fn bagging_peaks_hashes<'a, T: 'a + PartialEq + Debug + Clone, M: Merge<Item = T
 fn calculate_root<
     'a,
     T: 'a + PartialEq + Debug + Clone,
-    M: Merge<Item = T>,
+    M: NodeMerge<Item = T>,
+    B: PeakBagging<T>,
     I: Iterator<Item = &'a T>,
 >(
     leaves: Vec<(u64, T)>,
     mmr_size: u64,
     proof_iter: I,
-) -> Result<T> {
+) -> Result<B::Root> {
     let peaks_hashes = calculate_peaks_hashes::<_, M, _>(leaves, mmr_size, proof_iter)?;
-    bagging_peaks_hashes::<_, M>(peaks_hashes)
+    B::bag_peaks::<M>(peaks_hashes, mmr_size)
+}
+
+/// Positions of proof items for positions, in the order of `MMR::gen_proof`.
+/// Returns the positions and the count of right hand side peaks at the end of them,
+/// the peaks are bagged into one proof item if the count is greater than 1
+pub(crate) fn gen_proof_positions(
+    mmr_size: u64,
+    mut pos_list: Vec<u64>,
+) -> Result<(Vec<u64>, usize)> {
+    if pos_list.is_empty() {
+        return Err(Error::GenProofForInvalidLeaves);
+    }
+    if mmr_size == 1 && pos_list == [0] {
+        return Ok((Vec::new(), 0));
+    }
+    // ensure positions is sorted
+    pos_list.sort_unstable();
+    let mut proof_positions = Vec::new();
+    let mut bagging_track = 0;
+    for peak_pos in get_peaks(mmr_size) {
+        let pos_list: Vec<_> = take_while_vec(&mut pos_list, |&pos| pos <= peak_pos);
+        if pos_list.is_empty() {
+            bagging_track += 1;
+            // take peak root if no positions need to be proof
+            proof_positions.push(peak_pos);
+            continue;
+        }
+        bagging_track = 0;
+        // do nothing if position itself is the peak
+        if pos_list == [peak_pos] {
+            continue;
+        }
+        // positions can be leaves or internal nodes, queue is ordered by (height, pos)
+        let mut queue: Vec<_> = pos_list
+            .into_iter()
+            .map(|pos| (pos, pos_height_in_tree(pos)))
+            .collect();
+        queue.sort_by_key(|(pos, height)| (*height, *pos));
+        let mut queue: VecDeque<_> = queue.into();
+        while let Some((pos, height)) = queue.pop_front() {
+            if pos == peak_pos {
+                break;
+            }
+            // calculate sibling
+            let (sib_pos, parent_pos) = {
+                let next_height = pos_height_in_tree(pos + 1);
+                let sibling_offset = sibling_offset(height);
+                if next_height > height {
+                    // implies pos is right sibling
+                    (pos - sibling_offset, pos + 1)
+                } else {
+                    // pos is left sibling
+                    (pos + sibling_offset, pos + parent_offset(height))
+                }
+            };
+            if Some(&sib_pos) == queue.front().map(|(pos, _)| pos) {
+                // drop sibling
+                queue.pop_front();
+            } else {
+                proof_positions.push(sib_pos);
+            }
+            if parent_pos < peak_pos {
+                push_by_height(&mut queue, (parent_pos, height + 1), |(pos, height)| {
+                    (*height, *pos)
+                });
+            }
+        }
+    }
+    // ensure no remain positions
+    if !pos_list.is_empty() {
+        return Err(Error::GenProofForInvalidLeaves);
+    }
+    Ok((proof_positions, bagging_track))
+}
+
+/// insert into a queue ordered by (height, pos),
+/// parents are usually the largest one so we search from back
+fn push_by_height<E, K: Fn(&E) -> (u32, u64)>(queue: &mut VecDeque<E>, elem: E, key: K) {
+    let elem_key = key(&elem);
+    let index = queue
+        .iter()
+        .rposition(|e| key(e) < elem_key)
+        .map_or(0, |i| i + 1);
+    queue.insert(index, elem);
 }
 
 fn take_while_vec<T, P: Fn(&T) -> bool>(v: &mut Vec<T>, p: P) -> Vec<T> {
@@ -422,5 +842,5 @@ fn take_while_vec<T, P: Fn(&T) -> bool>(v: &mut Vec<T>, p: P) -> Vec<T> {
             return v.drain(..i).collect();
         }
     }
-    v.drain(..).collect()
+    core::mem::take(v)
 }
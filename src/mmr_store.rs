@@ -1,8 +1,12 @@
-use crate::{vec::Vec, Result};
+use crate::{vec::Vec, Error, Result};
 
 #[derive(Default)]
 pub struct MMRBatch<Elem, Store: MMRStore<Elem>> {
     memory_batch: Vec<(u64, Vec<Elem>)>,
+    // elems at or above this position are removed from the store on commit
+    truncate_to: Option<u64>,
+    // elems removed from the store on commit
+    removed: Vec<u64>,
     store: Store,
 }
 
@@ -10,6 +14,8 @@ impl<Elem: Clone, Store: MMRStore<Elem>> MMRBatch<Elem, Store> {
     pub fn new(store: Store) -> Self {
         MMRBatch {
             memory_batch: Vec::new(),
+            truncate_to: None,
+            removed: Vec::new(),
             store,
         }
     }
@@ -18,28 +24,99 @@ impl<Elem: Clone, Store: MMRStore<Elem>> MMRBatch<Elem, Store> {
         self.memory_batch.push((pos, elems));
     }
 
-    pub fn get_elem(&self, pos: u64) -> Result<Option<Elem>> {
+    /// remove elems from the store on commit, used to prune the MMR
+    pub fn remove(&mut self, pos_list: Vec<u64>) {
+        self.removed.extend(pos_list);
+    }
+
+    /// drop all elems at or above `mmr_size`, both pending and stored
+    pub fn truncate(&mut self, mmr_size: u64) {
+        self.removed.retain(|&pos| pos < mmr_size);
+        while let Some((start_pos, elems)) = self.memory_batch.last_mut() {
+            if *start_pos >= mmr_size {
+                self.memory_batch.pop();
+            } else {
+                elems.truncate((mmr_size - *start_pos) as usize);
+                return;
+            }
+        }
+        // no pending elems left, the rest must be removed from store
+        self.truncate_to = Some(
+            self.truncate_to
+                .map_or(mmr_size, |truncate_to| truncate_to.min(mmr_size)),
+        );
+    }
+
+    fn get_pending_elem(&self, pos: u64) -> Option<Elem> {
         for (start_pos, elems) in self.memory_batch.iter().rev() {
             if pos < *start_pos {
                 continue;
             } else if pos < start_pos + elems.len() as u64 {
-                return Ok(elems.get((pos - start_pos) as usize).cloned());
+                return elems.get((pos - start_pos) as usize).cloned();
             } else {
                 break;
             }
         }
+        None
+    }
+
+    // elems at or above `truncate_to` are removed from store
+    fn is_truncated(&self, pos: u64) -> bool {
+        self.truncate_to
+            .is_some_and(|truncate_to| pos >= truncate_to)
+    }
+
+    pub fn get_elem(&self, pos: u64) -> Result<Option<Elem>> {
+        if let Some(elem) = self.get_pending_elem(pos) {
+            return Ok(Some(elem));
+        }
+        if self.is_truncated(pos) {
+            return Ok(None);
+        }
         self.store.get_elem(pos)
     }
 
+    /// get elems of positions, pending elems are taken from memory
+    /// and the others are read from store in one batch
+    pub fn get_elems(&self, pos_list: &[u64]) -> Result<Vec<Option<Elem>>> {
+        let mut elems = Vec::with_capacity(pos_list.len());
+        let mut stored_pos_list = Vec::new();
+        for &pos in pos_list {
+            let elem = self.get_pending_elem(pos);
+            if elem.is_none() && !self.is_truncated(pos) {
+                stored_pos_list.push(pos);
+            }
+            elems.push(elem);
+        }
+        if stored_pos_list.is_empty() {
+            return Ok(elems);
+        }
+        let mut stored_elems = self.store.get_elems(&stored_pos_list)?.into_iter();
+        for (elem, &pos) in elems.iter_mut().zip(pos_list) {
+            if elem.is_none() && !self.is_truncated(pos) {
+                *elem = stored_elems.next().ok_or(Error::InconsistentStore)?;
+            }
+        }
+        Ok(elems)
+    }
+
     pub fn commit(self) -> Result<()> {
         let Self {
             mut store,
             memory_batch,
+            truncate_to,
+            removed,
         } = self;
+        if let Some(truncate_to) = truncate_to {
+            store.truncate(truncate_to)?;
+        }
         for (pos, elems) in memory_batch {
             store.append(pos, elems)?;
         }
-        Ok(())
+        if !removed.is_empty() {
+            store.remove(removed)?;
+        }
+        store.commit()
     }
 }
 
@@ -54,5 +131,23 @@ impl<Elem, Store: MMRStore<Elem>> IntoIterator for MMRBatch<Elem, Store> {
 
 pub trait MMRStore<Elem> {
     fn get_elem(&self, pos: u64) -> Result<Option<Elem>>;
+    /// Get elems of positions in one batch, stores which can read multiple elems
+    /// in one round trip should override it.
+    fn get_elems(&self, pos_list: &[u64]) -> Result<Vec<Option<Elem>>> {
+        pos_list.iter().map(|&pos| self.get_elem(pos)).collect()
+    }
     fn append(&mut self, pos: u64, elems: Vec<Elem>) -> Result<()>;
+    /// Remove all elems at or above `mmr_size`, used to rewind the MMR.
+    fn truncate(&mut self, _mmr_size: u64) -> Result<()> {
+        Err(Error::StoreError("truncate is not supported".into()))
+    }
+    /// Remove elems of pruned nodes, used to prune the MMR.
+    fn remove(&mut self, _pos_list: Vec<u64>) -> Result<()> {
+        Err(Error::StoreError("remove is not supported".into()))
+    }
+    /// Flush buffered writes, called once at the end of `MMRBatch::commit`, so a store
+    /// can write the truncate, appends and removes of a commit atomically.
+    fn commit(&mut self) -> Result<()> {
+        Ok(())
+    }
 }
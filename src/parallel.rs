@@ -0,0 +1,99 @@
+//! Parallel construction
+//!
+//! Build a MMR from a large set of leaves. Each perfect subtree is hashed in parallel,
+//! nodes are the same as pushing the leaves one by one.
+
+use crate::helper::leaf_count_to_mmr_size;
+use crate::vec;
+use crate::vec::Vec;
+use crate::{MMRStore, NodeMerge, Result};
+use rayon::prelude::*;
+
+// subtrees with fewer leaves are built in current thread
+const SEQUENTIAL_LEAVES: usize = 1024;
+
+/// build a perfect tree whose first node is at `offset` into `nodes` in position order,
+/// `nodes` has the size of the tree
+fn build_tree<T: Clone + Send + Sync, M: NodeMerge<Item = T>>(
+    leaves: &[T],
+    offset: u64,
+    height: u32,
+    nodes: &mut [Option<T>],
+) -> Result<()> {
+    if height == 0 {
+        nodes[0] = Some(M::hash_leaf(leaves[0].clone())?);
+        return Ok(());
+    }
+    let (left_leaves, right_leaves) = leaves.split_at(leaves.len() / 2);
+    // node count of a child tree
+    let child_size = (2u64 << (height - 1)) - 1;
+    let (children, parent) = nodes.split_at_mut(2 * child_size as usize);
+    let (left, right) = children.split_at_mut(child_size as usize);
+    let mut build_left = || build_tree::<T, M>(left_leaves, offset, height - 1, left);
+    let mut build_right =
+        || build_tree::<T, M>(right_leaves, offset + child_size, height - 1, right);
+    let (left_result, right_result) = if leaves.len() > SEQUENTIAL_LEAVES {
+        rayon::join(build_left, build_right)
+    } else {
+        (build_left(), build_right())
+    };
+    left_result?;
+    right_result?;
+    let left_root = children[child_size as usize - 1].as_ref();
+    let right_root = children[2 * child_size as usize - 1].as_ref();
+    parent[0] = Some(M::merge_node(
+        offset + 2 * child_size,
+        height,
+        left_root.expect("left root"),
+        right_root.expect("right root"),
+    )?);
+    Ok(())
+}
+
+/// Build a MMR from `leaves` in parallel, append nodes to the empty `store` in position order
+/// and commit the store, returns the MMR size. The MMR is the same as pushing leaves one by one.
+pub fn build_parallel<T, M, S>(leaves: &[T], mut store: S) -> Result<u64>
+where
+    T: Clone + Send + Sync,
+    M: NodeMerge<Item = T>,
+    S: MMRStore<T>,
+{
+    let mmr_size = leaf_count_to_mmr_size(leaves.len() as u64);
+    let mut nodes: Vec<Option<T>> = vec![None; mmr_size as usize];
+    // split leaves and nodes into perfect trees of decreasing heights
+    let mut trees = Vec::new();
+    let mut leaf_offset = 0;
+    let mut offset = 0;
+    let mut rest_nodes = &mut nodes[..];
+    for height in (0..usize::BITS).rev() {
+        let leaf_count = 1usize << height;
+        if leaves.len() - leaf_offset >= leaf_count {
+            let tree_size = (2u64 << height) - 1;
+            let (tree_nodes, rest) = rest_nodes.split_at_mut(tree_size as usize);
+            rest_nodes = rest;
+            trees.push((
+                &leaves[leaf_offset..leaf_offset + leaf_count],
+                offset,
+                height,
+                tree_nodes,
+            ));
+            leaf_offset += leaf_count;
+            offset += tree_size;
+        }
+    }
+    trees
+        .into_par_iter()
+        .map(|(leaves, offset, height, tree_nodes)| {
+            build_tree::<T, M>(leaves, offset, height, tree_nodes)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let nodes: Vec<T> = nodes
+        .into_iter()
+        .map(|node| node.expect("built node"))
+        .collect();
+    if !nodes.is_empty() {
+        store.append(0, nodes)?;
+    }
+    store.commit()?;
+    Ok(mmr_size)
+}
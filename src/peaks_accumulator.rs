@@ -0,0 +1,97 @@
+//! Peaks accumulator
+//!
+//! Keeps only the peaks of a MMR, which is enough to push new elems and calculate root,
+//! no MMRStore is needed.
+
+use crate::helper::{get_peaks, is_valid_mmr_size, pos_height_in_tree};
+use crate::vec::Vec;
+use crate::{Error, NodeMerge, PeakBagging, Result, RightToLeftBagging};
+use core::fmt::Debug;
+use core::marker::PhantomData;
+
+#[derive(Debug, Clone)]
+pub struct PeaksAccumulator<T, M, B = RightToLeftBagging> {
+    mmr_size: u64,
+    // peaks from left to right
+    peaks: Vec<T>,
+    merge: PhantomData<M>,
+    bagging: PhantomData<B>,
+}
+
+impl<T: Clone + PartialEq + Debug, M: NodeMerge<Item = T>, B: PeakBagging<T>> Default
+    for PeaksAccumulator<T, M, B>
+{
+    fn default() -> Self {
+        PeaksAccumulator {
+            mmr_size: 0,
+            peaks: Vec::new(),
+            merge: PhantomData,
+            bagging: PhantomData,
+        }
+    }
+}
+
+impl<T: Clone + PartialEq + Debug, M: NodeMerge<Item = T>, B: PeakBagging<T>>
+    PeaksAccumulator<T, M, B>
+{
+    /// create accumulator from peak hashes of a MMR, peaks are ordered from left to right
+    pub fn new(mmr_size: u64, peaks: Vec<T>) -> Result<Self> {
+        let peaks_count = if mmr_size == 0 {
+            0
+        } else {
+            get_peaks(mmr_size).len()
+        };
+        if !is_valid_mmr_size(mmr_size) || peaks.len() != peaks_count {
+            return Err(Error::InvalidMMRSize);
+        }
+        Ok(PeaksAccumulator {
+            mmr_size,
+            peaks,
+            merge: PhantomData,
+            bagging: PhantomData,
+        })
+    }
+
+    pub fn mmr_size(&self) -> u64 {
+        self.mmr_size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mmr_size == 0
+    }
+
+    pub fn peaks(&self) -> &[T] {
+        &self.peaks
+    }
+
+    pub fn into_peaks(self) -> Vec<T> {
+        self.peaks
+    }
+
+    // push a element and return position
+    pub fn push(&mut self, elem: T) -> Result<u64> {
+        // position of new elem
+        let elem_pos = self.mmr_size;
+        let mut elem = M::hash_leaf(elem)?;
+        let mut height = 0u32;
+        let mut pos = elem_pos;
+        // merge with left peaks if next pos heigher than current
+        while pos_height_in_tree(pos + 1) > height {
+            pos += 1;
+            let left_elem = self.peaks.pop().ok_or(Error::InconsistentStore)?;
+            elem = M::merge_node(pos, height + 1, &left_elem, &elem)?;
+            height += 1
+        }
+        self.peaks.push(elem);
+        // update mmr_size
+        self.mmr_size = pos + 1;
+        Ok(elem_pos)
+    }
+
+    pub fn get_root(&self) -> Result<B::Root> {
+        if self.mmr_size == 0 {
+            return Err(Error::GetRootOnEmpty);
+        }
+        B::bag_peaks::<M>(self.peaks.clone(), self.mmr_size)
+    }
+}
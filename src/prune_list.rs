@@ -0,0 +1,121 @@
+//! Prune list
+//!
+//! Keeps the roots of pruned subtrees. Nodes under a pruned root are removed from the store,
+//! the root itself is kept so the MMR can still compute peaks, push new elems and
+//! generate proofs for the leaves which are not pruned.
+//! When both siblings are pruned, their parent becomes the pruned root and the siblings
+//! are removed too, so the list stays compact.
+//!
+//! references:
+//! https://github.com/mimblewimble/grin/blob/master/store/src/prune_list.rs
+
+use crate::collections::BTreeSet;
+use crate::helper::{parent_offset, pos_height_in_tree, sibling_offset};
+use crate::vec::Vec;
+use crate::{Error, Result};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PruneList {
+    pruned_roots: BTreeSet<u64>,
+}
+
+// the lowest position in the subtree of `pos`
+fn subtree_start(pos: u64) -> u64 {
+    pos + 2 - parent_offset(pos_height_in_tree(pos))
+}
+
+impl PruneList {
+    pub fn new() -> Self {
+        PruneList::default()
+    }
+
+    /// build prune list from roots returned by `pruned_roots`
+    pub fn from_pruned_roots(pruned_roots: Vec<u64>) -> Self {
+        PruneList {
+            pruned_roots: pruned_roots.into_iter().collect(),
+        }
+    }
+
+    pub fn pruned_roots(&self) -> Vec<u64> {
+        self.pruned_roots.iter().cloned().collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pruned_roots.is_empty()
+    }
+
+    /// return the root of pruned subtree which contains `pos`
+    pub fn get_pruned_root(&self, pos: u64) -> Option<u64> {
+        // pruned subtrees never overlap, so the nearest root is the only candidate
+        self.pruned_roots
+            .range(pos..)
+            .next()
+            .filter(|&&root| subtree_start(root) <= pos)
+            .cloned()
+    }
+
+    /// the node is pruned, it may still be stored if it is a pruned root
+    pub fn is_pruned(&self, pos: u64) -> bool {
+        self.get_pruned_root(pos).is_some()
+    }
+
+    /// the node is under a pruned root and is removed from store
+    pub fn is_removed(&self, pos: u64) -> bool {
+        matches!(self.get_pruned_root(pos), Some(pruned_root) if pruned_root != pos)
+    }
+
+    /// prune the subtree of `pos` in a MMR of `mmr_size`,
+    /// return positions of nodes which should be removed from store
+    pub fn add(&mut self, pos: u64, mmr_size: u64) -> Vec<u64> {
+        if self.is_pruned(pos) {
+            return Vec::new();
+        }
+        // compact pruned roots, merge the node with its pruned sibling
+        let mut root = pos;
+        let mut height = pos_height_in_tree(pos);
+        loop {
+            let (sib_pos, parent_pos) = if pos_height_in_tree(root + 1) > height {
+                // implies root is right sibling
+                (root - sibling_offset(height), root + 1)
+            } else {
+                // root is left sibling
+                (root + sibling_offset(height), root + parent_offset(height))
+            };
+            if sib_pos >= mmr_size || !self.pruned_roots.contains(&sib_pos) {
+                break;
+            }
+            debug_assert!(parent_pos < mmr_size);
+            root = parent_pos;
+            height += 1;
+        }
+
+        // collect stored nodes under the new root, old pruned roots under it are removed
+        let start = subtree_start(root);
+        let old_roots: Vec<u64> = self.pruned_roots.range(start..root).cloned().collect();
+        let mut removed = Vec::new();
+        let mut next_pos = start;
+        for old_root in old_roots {
+            self.pruned_roots.remove(&old_root);
+            removed.extend(next_pos..subtree_start(old_root));
+            removed.push(old_root);
+            next_pos = old_root + 1;
+        }
+        removed.extend(next_pos..root);
+        self.pruned_roots.insert(root);
+        removed
+    }
+
+    /// drop pruned roots at or above `mmr_size`, used to rewind the MMR.
+    /// return error if nodes below `mmr_size` are already removed
+    pub fn truncate(&mut self, mmr_size: u64) -> Result<()> {
+        if self
+            .pruned_roots
+            .range(mmr_size..)
+            .any(|&root| subtree_start(root) < mmr_size)
+        {
+            return Err(Error::RewindPrunedNodes);
+        }
+        self.pruned_roots.split_off(&mmr_size);
+        Ok(())
+    }
+}
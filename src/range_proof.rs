@@ -0,0 +1,201 @@
+//! Range proof
+//!
+//! Prove a contiguous range of leaves, such as every header of an epoch. Nodes covered by
+//! the range are calculated from the leaves, so the proof only contains the siblings on
+//! the boundaries of the range and the peaks out of it, its size grows with log n rather
+//! than the length of the range.
+//!
+//! Proof items are ordered by peaks from left to right:
+//! 1. peaks on the left hand side of the range
+//! 2. boundary siblings of each peak touched by the range, in the order of a depth-first walk
+//! 3. peaks on the right hand side of the range, which may be bagged by the bagging strategy
+
+use crate::helper::{mmr_size_to_leaf_count, parent_offset, peaks_iter, pos_height_in_tree};
+use crate::vec::Vec;
+use crate::{Error, NodeMerge, PeakBagging, Result, RightToLeftBagging};
+use core::fmt::Debug;
+use core::marker::PhantomData;
+
+// a perfect tree in the MMR, covers leaves from `start_leaf`
+#[derive(Clone, Copy)]
+pub(crate) struct Subtree {
+    pub(crate) pos: u64,
+    pub(crate) height: u32,
+    pub(crate) start_leaf: u64,
+}
+
+impl Subtree {
+    pub(crate) fn end_leaf(&self) -> u64 {
+        self.start_leaf + (1 << self.height)
+    }
+
+    // the subtree has no leaves in the range
+    fn is_disjoint(&self, start_leaf: u64, end_leaf: u64) -> bool {
+        self.end_leaf() <= start_leaf || self.start_leaf >= end_leaf
+    }
+
+    // all leaves of the subtree are in the range
+    fn is_covered(&self, start_leaf: u64, end_leaf: u64) -> bool {
+        self.start_leaf >= start_leaf && self.end_leaf() <= end_leaf
+    }
+
+    pub(crate) fn children(&self) -> (Subtree, Subtree) {
+        let height = self.height - 1;
+        let left = Subtree {
+            pos: self.pos - parent_offset(height),
+            height,
+            start_leaf: self.start_leaf,
+        };
+        let right = Subtree {
+            pos: self.pos - 1,
+            height,
+            start_leaf: self.start_leaf + (1 << height),
+        };
+        (left, right)
+    }
+}
+
+// peaks from left to right as subtrees
+pub(crate) fn peak_subtrees(mmr_size: u64) -> impl Iterator<Item = Subtree> {
+    let mut start_leaf = 0;
+    peaks_iter(mmr_size).map(move |pos| {
+        let peak = Subtree {
+            pos,
+            height: pos_height_in_tree(pos),
+            start_leaf,
+        };
+        start_leaf = peak.end_leaf();
+        peak
+    })
+}
+
+// the range must be non-empty and in the MMR
+fn check_range(mmr_size: u64, start_leaf: u64, end_leaf: u64) -> Result<()> {
+    if start_leaf >= end_leaf || end_leaf > mmr_size_to_leaf_count(mmr_size)? {
+        return Err(Error::GenProofForInvalidLeaves);
+    }
+    Ok(())
+}
+
+// push positions of subtrees which are disjoint with the range, from left to right
+fn collect_siblings(tree: Subtree, start_leaf: u64, end_leaf: u64, positions: &mut Vec<u64>) {
+    if tree.is_disjoint(start_leaf, end_leaf) {
+        positions.push(tree.pos);
+    } else if !tree.is_covered(start_leaf, end_leaf) {
+        let (left, right) = tree.children();
+        collect_siblings(left, start_leaf, end_leaf, positions);
+        collect_siblings(right, start_leaf, end_leaf, positions);
+    }
+}
+
+/// Positions of proof items for leaves in `start_leaf..end_leaf`, in the order of proof items.
+/// Returns the positions and the count of right hand side peaks at the end of them
+pub(crate) fn range_proof_positions(
+    mmr_size: u64,
+    start_leaf: u64,
+    end_leaf: u64,
+) -> Result<(Vec<u64>, usize)> {
+    check_range(mmr_size, start_leaf, end_leaf)?;
+    let mut proof_positions = Vec::new();
+    let mut rhs_peaks_count = 0;
+    for peak in peak_subtrees(mmr_size) {
+        if peak.start_leaf >= end_leaf {
+            rhs_peaks_count += 1;
+        }
+        collect_siblings(peak, start_leaf, end_leaf, &mut proof_positions);
+    }
+    Ok((proof_positions, rhs_peaks_count))
+}
+
+// calculate root of a subtree from leaves of the range and proof items
+fn calculate_subtree_root<'a, T: 'a + Clone, M: NodeMerge<Item = T>, I: Iterator<Item = &'a T>>(
+    tree: Subtree,
+    start_leaf: u64,
+    leaves: &[T],
+    proof_iter: &mut I,
+) -> Result<T> {
+    let end_leaf = start_leaf + leaves.len() as u64;
+    if tree.is_disjoint(start_leaf, end_leaf) {
+        proof_iter.next().cloned().ok_or(Error::CorruptedProof)
+    } else if tree.height == 0 {
+        M::hash_leaf(leaves[(tree.start_leaf - start_leaf) as usize].clone())
+    } else {
+        let (left, right) = tree.children();
+        let left_root = calculate_subtree_root::<_, M, _>(left, start_leaf, leaves, proof_iter)?;
+        let right_root = calculate_subtree_root::<_, M, _>(right, start_leaf, leaves, proof_iter)?;
+        M::merge_node(tree.pos, tree.height, &left_root, &right_root)
+    }
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RangeProof<T, M, B = RightToLeftBagging> {
+    mmr_size: u64,
+    proof: Vec<T>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    merge: PhantomData<M>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    bagging: PhantomData<B>,
+}
+
+impl<T: PartialEq + Debug + Clone, M: NodeMerge<Item = T>, B: PeakBagging<T>> RangeProof<T, M, B> {
+    pub fn new(mmr_size: u64, proof: Vec<T>) -> Self {
+        RangeProof {
+            mmr_size,
+            proof,
+            merge: PhantomData,
+            bagging: PhantomData,
+        }
+    }
+
+    pub fn mmr_size(&self) -> u64 {
+        self.mmr_size
+    }
+
+    pub fn proof_items(&self) -> &[T] {
+        &self.proof
+    }
+
+    /// calculate root from leaves from `start_leaf` in order, leaves are the elems
+    /// pushed into MMR, they are hashed by `M::hash_leaf` as in push
+    pub fn calculate_root(&self, start_leaf: u64, leaves: &[T]) -> Result<B::Root> {
+        let end_leaf = start_leaf
+            .checked_add(leaves.len() as u64)
+            .ok_or(Error::CorruptedProof)?;
+        check_range(self.mmr_size, start_leaf, end_leaf).map_err(|err| match err {
+            Error::GenProofForInvalidLeaves => Error::CorruptedProof,
+            err => err,
+        })?;
+        let mut proof_iter = self.proof.iter();
+        let mut peaks = Vec::new();
+        let mut rhs_peaks_count = 0;
+        for peak in peak_subtrees(self.mmr_size) {
+            if peak.start_leaf >= end_leaf {
+                // right hand side peaks are taken as they are bagged
+                rhs_peaks_count += 1;
+                continue;
+            }
+            peaks.push(calculate_subtree_root::<_, M, _>(
+                peak,
+                start_leaf,
+                leaves,
+                &mut proof_iter,
+            )?);
+        }
+        let rhs_peaks: Vec<T> = proof_iter.cloned().collect();
+        if rhs_peaks.len() > rhs_peaks_count || (rhs_peaks.is_empty() && rhs_peaks_count > 0) {
+            return Err(Error::CorruptedProof);
+        }
+        peaks.extend(rhs_peaks);
+        B::bag_peaks::<M>(peaks, self.mmr_size)
+    }
+
+    /// verify leaves from `start_leaf` in order
+    pub fn verify(&self, root: B::Root, start_leaf: u64, leaves: &[T]) -> Result<bool>
+    where
+        B::Root: PartialEq,
+    {
+        self.calculate_root(start_leaf, leaves)
+            .map(|calculated_root| calculated_root == root)
+    }
+}
@@ -0,0 +1,229 @@
+//! Rendering
+//!
+//! Draw an MMR of a given size as Graphviz DOT or as ASCII art, for debugging.
+//! Nodes can be labeled by elems in store, and nodes of a `MerkleProof` are highlighted:
+//!
+//! | node          | ASCII     | DOT         |
+//! |---------------|-----------|-------------|
+//! | proven node   | `<pos>`   | light blue  |
+//! | proof item    | `[pos]`   | orange      |
+//! | bagged peak   | `{pos}`   | grey        |
+//!
+//! Proof items are listed in the order of the proof, bagged peaks share one proof item.
+
+use crate::collections::BTreeMap;
+use crate::helper::is_valid_mmr_size;
+use crate::mmr::gen_proof_positions;
+use crate::range_proof::{peak_subtrees, Subtree};
+use crate::{Error, MMRStore, MerkleProof, NodeMerge, PeakBagging, Result};
+use std::fmt::Debug;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Highlight {
+    /// A node proven by the proof, such as a leaf
+    Proven,
+    /// A node in the proof, with the index of proof item
+    ProofItem(usize),
+    /// A right hand side peak bagged into the proof item of the index
+    BaggedPeak(usize),
+}
+
+#[derive(Debug, Clone)]
+pub struct MMRRender {
+    mmr_size: u64,
+    labels: BTreeMap<u64, String>,
+    highlights: BTreeMap<u64, Highlight>,
+}
+
+impl MMRRender {
+    pub fn new(mmr_size: u64) -> Result<Self> {
+        if !is_valid_mmr_size(mmr_size) {
+            return Err(Error::InvalidMMRSize);
+        }
+        Ok(MMRRender {
+            mmr_size,
+            labels: BTreeMap::new(),
+            highlights: BTreeMap::new(),
+        })
+    }
+
+    /// label nodes by elems in store, nodes missing in store are not labeled
+    pub fn with_labels<T, S: MMRStore<T>, F: Fn(&T) -> String>(
+        mut self,
+        store: &S,
+        label: F,
+    ) -> Result<Self> {
+        let pos_list: Vec<u64> = (0..self.mmr_size).collect();
+        for (pos, elem) in pos_list.iter().zip(store.get_elems(&pos_list)?) {
+            if let Some(elem) = elem {
+                self.labels.insert(*pos, label(&elem));
+            }
+        }
+        Ok(self)
+    }
+
+    /// highlight `pos_list`, the proof items and the bagged peaks of `proof` of them
+    pub fn with_proof<T: PartialEq + Debug + Clone, M: NodeMerge<Item = T>, B: PeakBagging<T>>(
+        mut self,
+        proof: &MerkleProof<T, M, B>,
+        pos_list: &[u64],
+    ) -> Result<Self> {
+        if proof.mmr_size() != self.mmr_size {
+            return Err(Error::InvalidMMRSize);
+        }
+        let (proof_positions, bagging_track) =
+            gen_proof_positions(self.mmr_size, pos_list.to_vec())?;
+        // right hand side peaks are bagged into the last proof item if the strategy compresses them
+        let bagged_count = if proof.proof_items().len() < proof_positions.len() {
+            bagging_track
+        } else {
+            0
+        };
+        let known_count = proof_positions.len() - bagged_count;
+        self.highlights.clear();
+        for &pos in pos_list {
+            self.highlights.insert(pos, Highlight::Proven);
+        }
+        for (index, pos) in proof_positions.into_iter().enumerate() {
+            let highlight = if index < known_count {
+                Highlight::ProofItem(index)
+            } else {
+                Highlight::BaggedPeak(known_count)
+            };
+            self.highlights.insert(pos, highlight);
+        }
+        Ok(self)
+    }
+
+    pub fn highlight(&self, pos: u64) -> Option<Highlight> {
+        self.highlights.get(&pos).cloned()
+    }
+
+    // all nodes from left to right by depth-first walk
+    fn nodes(&self) -> Vec<Subtree> {
+        fn walk(tree: Subtree, nodes: &mut Vec<Subtree>) {
+            if tree.height > 0 {
+                let (left, right) = tree.children();
+                walk(left, nodes);
+                walk(right, nodes);
+            }
+            nodes.push(tree);
+        }
+        let mut nodes = Vec::new();
+        for peak in peak_subtrees(self.mmr_size) {
+            walk(peak, &mut nodes);
+        }
+        nodes
+    }
+
+    /// Graphviz DOT, edges point from parents to children
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph mmr {\n    node [shape=box];\n");
+        let nodes = self.nodes();
+        for node in &nodes {
+            let mut label = node.pos.to_string();
+            if let Some(elem_label) = self.labels.get(&node.pos) {
+                write!(label, "\\n{}", escape(elem_label)).expect("write string");
+            }
+            let style = match self.highlight(node.pos) {
+                Some(Highlight::Proven) => ", style=filled, fillcolor=lightblue".to_string(),
+                Some(Highlight::ProofItem(index)) => {
+                    write!(label, "\\nproof[{}]", index).expect("write string");
+                    ", style=filled, fillcolor=orange".to_string()
+                }
+                Some(Highlight::BaggedPeak(index)) => {
+                    write!(label, "\\nbagged into proof[{}]", index).expect("write string");
+                    ", style=filled, fillcolor=grey".to_string()
+                }
+                None => String::new(),
+            };
+            writeln!(dot, "    n{} [label=\"{}\"{}];", node.pos, label, style)
+                .expect("write string");
+            if node.height > 0 {
+                let (left, right) = node.children();
+                writeln!(dot, "    n{} -> n{};", node.pos, left.pos).expect("write string");
+                writeln!(dot, "    n{} -> n{};", node.pos, right.pos).expect("write string");
+            }
+        }
+        // nodes of the same height are drawn in the same row
+        let mut rows: BTreeMap<u32, Vec<u64>> = BTreeMap::new();
+        for node in &nodes {
+            rows.entry(node.height).or_default().push(node.pos);
+        }
+        for row in rows.values() {
+            let row: Vec<String> = row.iter().map(|pos| format!("n{}", pos)).collect();
+            writeln!(dot, "    {{ rank=same; {}; }}", row.join("; ")).expect("write string");
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// ASCII art with the highest nodes on top, every parent is centered above its children.
+    /// Labels and the order of proof items are listed below the tree
+    pub fn to_ascii(&self) -> String {
+        let nodes = self.nodes();
+        let cells: BTreeMap<u64, String> = nodes
+            .iter()
+            .map(|node| (node.pos, self.ascii_cell(node.pos)))
+            .collect();
+        // leaves are 2 units apart, a parent is in the middle of its leaves
+        let cell_width = cells.values().map(|cell| cell.len()).max().unwrap_or(0);
+        let unit = (cell_width + 2) / 2;
+        let max_height = nodes.iter().map(|node| node.height).max().unwrap_or(0);
+        let mut rows = vec![String::new(); max_height as usize + 1];
+        for node in &nodes {
+            let row = &mut rows[(max_height - node.height) as usize];
+            let x = (2 * node.start_leaf + (1 << node.height) - 1) as usize * unit;
+            let cell = &cells[&node.pos];
+            // center the cell at x
+            let start = (x + cell_width / 2).saturating_sub(cell.len() / 2);
+            while row.len() < start {
+                row.push(' ');
+            }
+            row.push_str(cell);
+        }
+        let mut ascii = String::new();
+        for row in rows {
+            ascii.push_str(row.trim_end());
+            ascii.push('\n');
+        }
+        for (pos, label) in &self.labels {
+            writeln!(ascii, "{}: {}", pos, label).expect("write string");
+        }
+        let mut proof_items: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+        for (pos, highlight) in &self.highlights {
+            match highlight {
+                Highlight::ProofItem(index) | Highlight::BaggedPeak(index) => {
+                    proof_items
+                        .entry(*index)
+                        .or_default()
+                        .push(cells[pos].clone());
+                }
+                Highlight::Proven => {}
+            }
+        }
+        if !proof_items.is_empty() {
+            let items: Vec<String> = proof_items
+                .into_iter()
+                .map(|(index, cells)| format!("{}={}", index, cells.join("+")))
+                .collect();
+            writeln!(ascii, "proof: {}", items.join(" ")).expect("write string");
+        }
+        ascii
+    }
+
+    fn ascii_cell(&self, pos: u64) -> String {
+        match self.highlight(pos) {
+            Some(Highlight::Proven) => format!("<{}>", pos),
+            Some(Highlight::ProofItem(_)) => format!("[{}]", pos),
+            Some(Highlight::BaggedPeak(_)) => format!("{{{}}}", pos),
+            None => pos.to_string(),
+        }
+    }
+}
+
+// escape a label in a quoted DOT string
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,246 @@
+//! Leaf sampling
+//!
+//! Sample leaves of a weighted MMR by weight targets derived from the root, like
+//! the non-interactive FlyClient protocol. The prover can't choose the samples, since
+//! they are derived from the root by a random oracle (Fiat-Shamir), and the verifier
+//! recomputes them from the root and checks every target falls into an opened leaf.
+//!
+//! The root must carry the total weight, that is, `merge_peaks` sums weights as well.
+//!
+//! references:
+//! https://eprint.iacr.org/2019/226.pdf
+
+use crate::collections::BTreeMap;
+use crate::helper::{
+    get_peaks, leaf_index_to_pos, mmr_size_to_leaf_count, parent_offset, pos_height_in_tree,
+    sibling_offset,
+};
+use crate::mmr::gen_proof_positions;
+use crate::vec;
+use crate::vec::Vec;
+use crate::{Error, MerkleProof, NodeMerge, PeakBagging, Result, RightToLeftBagging, Weighted};
+use core::fmt::Debug;
+
+/// Derive pseudo-random numbers from the root, such as the first 8 bytes of `H(root || index)`
+pub trait RandomOracle<R> {
+    fn random(root: &R, index: u32) -> u64;
+}
+
+/// Distribution of sampled weights
+pub trait SampleDistribution<W> {
+    /// Map `random`, a uniform number of `0..2^64`, to a weight target in `0..total_weight`
+    fn sample(&self, random: u64, total_weight: W) -> W;
+}
+
+/// Sample weights uniformly
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct UniformDistribution;
+
+impl SampleDistribution<u64> for UniformDistribution {
+    fn sample(&self, random: u64, total_weight: u64) -> u64 {
+        ((u128::from(random) * u128::from(total_weight)) >> 64) as u64
+    }
+}
+
+/// Sample weights by the cdf of FlyClient, recent leaves are sampled more often.
+/// `random` is mapped to the fraction `1 - delta^y` of total weight, `y` is uniform in `0..1`,
+/// so the last `delta` fraction of weight is never sampled and should be checked by other means.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlyClientDistribution {
+    delta: f64,
+}
+
+#[cfg(feature = "std")]
+impl FlyClientDistribution {
+    /// `delta` must be in `(0, 1]`, such as the result of `vd_calculate_delta`,
+    /// a zero delta maps every sample to the total weight
+    pub fn new(delta: f64) -> Result<Self> {
+        if !(delta > 0.0 && delta <= 1.0) {
+            return Err(Error::InvalidDelta);
+        }
+        Ok(FlyClientDistribution { delta })
+    }
+
+    /// Fraction of total weight in the suffix which the verifier checks in full,
+    /// such as the last `L` headers of FlyClient, the rest of weight is sampled.
+    /// An empty MMR or a suffix heavier than the total checks everything,
+    /// an empty suffix of a non-empty MMR is an error since the delta would be 0
+    pub fn vd_calculate_delta(suffix_weight: u64, total_weight: u64) -> Result<f64> {
+        if suffix_weight >= total_weight {
+            return Ok(1.0);
+        }
+        if suffix_weight == 0 {
+            return Err(Error::InvalidDelta);
+        }
+        Ok(suffix_weight as f64 / total_weight as f64)
+    }
+
+    pub fn delta(&self) -> f64 {
+        self.delta
+    }
+}
+
+#[cfg(feature = "std")]
+impl SampleDistribution<u64> for FlyClientDistribution {
+    fn sample(&self, random: u64, total_weight: u64) -> u64 {
+        let y = random as f64 / 2f64.powi(64);
+        let fraction = 1.0 - self.delta.powf(y);
+        // rounding of float may reach the total weight
+        ((fraction * total_weight as f64) as u64).min(total_weight.saturating_sub(1))
+    }
+}
+
+/// Weight targets derived from the root
+pub(crate) fn sample_weights<R, O, D>(
+    root: &R,
+    distribution: &D,
+    sample_count: u32,
+) -> Vec<R::Weight>
+where
+    R: Weighted,
+    O: RandomOracle<R>,
+    D: SampleDistribution<R::Weight>,
+{
+    let total_weight = root.weight();
+    (0..sample_count)
+        .map(|index| distribution.sample(O::random(root, index), total_weight))
+        .collect()
+}
+
+// weight of a node, nodes which are not known are the sum of their children
+fn node_weight<W: Copy + core::ops::Add<Output = W>>(
+    known_weights: &BTreeMap<u64, W>,
+    pos: u64,
+) -> Result<W> {
+    if let Some(&weight) = known_weights.get(&pos) {
+        return Ok(weight);
+    }
+    let height = pos_height_in_tree(pos);
+    if height == 0 {
+        return Err(Error::CorruptedProof);
+    }
+    let left_weight = node_weight(known_weights, pos - parent_offset(height - 1))?;
+    let right_weight = node_weight(known_weights, pos - 1)?;
+    Ok(left_weight + right_weight)
+}
+
+/// Proof of sampled leaves
+#[derive(Debug)]
+pub struct SampleProof<T, M, B = RightToLeftBagging> {
+    leaf_indices: Vec<u64>,
+    proof: MerkleProof<T, M, B>,
+}
+
+impl<T: PartialEq + Debug + Clone + Weighted, M: NodeMerge<Item = T>, B: PeakBagging<T>>
+    SampleProof<T, M, B>
+{
+    pub fn new(leaf_indices: Vec<u64>, proof: MerkleProof<T, M, B>) -> Self {
+        SampleProof {
+            leaf_indices,
+            proof,
+        }
+    }
+
+    /// indices of sampled leaves in ascending order
+    pub fn leaf_indices(&self) -> &[u64] {
+        &self.leaf_indices
+    }
+
+    pub fn proof(&self) -> &MerkleProof<T, M, B> {
+        &self.proof
+    }
+
+    /// cumulative weight intervals of leaves, weights of nodes come from leaves and proof items
+    fn weight_intervals(&self, leaves: &[(u64, T)]) -> Result<Vec<(T::Weight, T::Weight)>> {
+        let mmr_size = self.proof.mmr_size();
+        let pos_list = leaves.iter().map(|(pos, _)| *pos).collect();
+        let (proof_positions, bagging_track) = gen_proof_positions(mmr_size, pos_list)?;
+        // bagged right hand side peaks are never on the left of a leaf
+        let known_count = if bagging_track > 1 {
+            proof_positions.len() - bagging_track
+        } else {
+            proof_positions.len()
+        };
+        let known_weights: BTreeMap<u64, T::Weight> = proof_positions
+            .into_iter()
+            .zip(self.proof.proof_items())
+            .take(known_count)
+            .map(|(pos, item)| (pos, item.weight()))
+            .chain(leaves.iter().map(|(pos, leaf)| (*pos, leaf.weight())))
+            .collect();
+        let peaks = get_peaks(mmr_size);
+        leaves
+            .iter()
+            .map(|(leaf_pos, leaf)| {
+                let mut left_weight = T::Weight::default();
+                let mut peak_height = 0;
+                for &peak_pos in &peaks {
+                    if peak_pos >= *leaf_pos {
+                        peak_height = pos_height_in_tree(peak_pos);
+                        break;
+                    }
+                    left_weight = left_weight + node_weight(&known_weights, peak_pos)?;
+                }
+                let mut pos = *leaf_pos;
+                for height in 0..peak_height {
+                    if pos_height_in_tree(pos + 1) > height {
+                        // pos is the right sibling
+                        let sibling_pos = pos - sibling_offset(height);
+                        left_weight = left_weight + node_weight(&known_weights, sibling_pos)?;
+                        pos += 1;
+                    } else {
+                        pos += parent_offset(height);
+                    }
+                }
+                Ok((left_weight, left_weight + leaf.weight()))
+            })
+            .collect()
+    }
+
+    /// verify `leaves` of `leaf_indices` are exactly the leaves sampled from `root`
+    /// 1. verify leaves by the merkle proof
+    /// 2. recompute weight targets from the root
+    /// 3. every target must fall into an opened leaf, and every opened leaf must be sampled
+    pub fn verify<O: RandomOracle<B::Root>, D: SampleDistribution<T::Weight>>(
+        &self,
+        root: B::Root,
+        distribution: &D,
+        sample_count: u32,
+        leaves: Vec<T>,
+    ) -> Result<bool>
+    where
+        B::Root: PartialEq + Clone + Weighted<Weight = T::Weight>,
+    {
+        if leaves.len() != self.leaf_indices.len()
+            || self.leaf_indices.windows(2).any(|pair| pair[0] >= pair[1])
+        {
+            return Err(Error::CorruptedProof);
+        }
+        // indices are ascending, so the last one is the largest
+        let leaf_count = mmr_size_to_leaf_count(self.proof.mmr_size())?;
+        if matches!(self.leaf_indices.last(), Some(&leaf_index) if leaf_index >= leaf_count) {
+            return Err(Error::CorruptedProof);
+        }
+        let leaves: Vec<(u64, T)> = self
+            .leaf_indices
+            .iter()
+            .map(|&leaf_index| leaf_index_to_pos(leaf_index))
+            .zip(leaves)
+            .collect();
+        if !self.proof.verify(root.clone(), leaves.clone())? {
+            return Ok(false);
+        }
+        let intervals = self.weight_intervals(&leaves)?;
+        let mut sampled = vec![false; intervals.len()];
+        for weight in sample_weights::<_, O, D>(&root, distribution, sample_count) {
+            // intervals are ordered since leaves are
+            let index = intervals.partition_point(|&(_, end)| end <= weight);
+            match intervals.get(index) {
+                Some(&(start, _)) if start <= weight => sampled[index] = true,
+                _ => return Ok(false),
+            }
+        }
+        Ok(sampled.into_iter().all(|sampled| sampled))
+    }
+}
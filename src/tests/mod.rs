@@ -1,8 +1,27 @@
 mod test_accumulate_headers;
+mod test_ancestry_proof;
+#[cfg(feature = "async")]
+mod test_async_mmr;
+mod test_bagging;
+mod test_cached_store;
+mod test_codec;
+mod test_file_store;
 mod test_helper;
+mod test_kv_store;
 mod test_mmr;
+mod test_node_merge;
+#[cfg(feature = "rayon")]
+mod test_parallel;
+mod test_peaks_accumulator;
+mod test_prune;
+mod test_range_proof;
+mod test_render;
+mod test_try_merge;
+mod test_update_proof;
+mod test_validate;
+mod test_verifier;
 
-use crate::Merge;
+use crate::{CodecError, ElemCodec, FixedSizeElemCodec, Merge, Result};
 use blake2b_rs::{Blake2b, Blake2bBuilder};
 use bytes::Bytes;
 
@@ -35,3 +54,22 @@ impl Merge for MergeNumberHash {
         NumberHash(hash.to_vec().into())
     }
 }
+
+struct NumberHashCodec;
+
+impl ElemCodec for NumberHashCodec {
+    type Item = NumberHash;
+    fn encode(item: &Self::Item, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&item.0);
+    }
+    fn decode(bytes: &[u8]) -> Result<(Self::Item, usize)> {
+        if bytes.len() < 32 {
+            return Err(CodecError::Truncated.into());
+        }
+        Ok((NumberHash(bytes[..32].to_vec().into()), 32))
+    }
+}
+
+impl FixedSizeElemCodec for NumberHashCodec {
+    const SIZE: usize = 32;
+}
@@ -1,6 +1,13 @@
 use super::new_blake2b;
-use crate::{leaf_index_to_pos, util::MemStore, MMRStore, Merge, MerkleProof, Result, MMR};
+use crate::{
+    leaf_index_to_mmr_size, leaf_index_to_pos, util::MemStore, ElemCodec, Error, FixedBytesCodec,
+    FixedSizeElemCodec, FlyClientDistribution, MMRStore, Merge, MerkleProof, RandomOracle, Result,
+    SampleDistribution, SampleProof, UniformDistribution, WeightProof, Weighted, MMR,
+};
 use bytes::Bytes;
+use proptest::prelude::*;
+use rand::{thread_rng, Rng};
+use std::convert::TryInto;
 use std::fmt::{self, Debug};
 
 #[derive(Clone)]
@@ -40,23 +47,29 @@ struct HashWithTD {
     td: u64,
 }
 
-impl HashWithTD {
-    fn serialize(&self) -> Bytes {
-        let mut data = self.hash.clone();
-        data.extend(&self.td.to_le_bytes());
-        data
-    }
+struct HashWithTDCodec;
 
-    fn deserialize(mut data: Bytes) -> Self {
-        assert_eq!(data.len(), 40);
-        let mut td_bytes = [0u8; 8];
-        td_bytes.copy_from_slice(&data[32..]);
-        let td = u64::from_le_bytes(td_bytes);
-        data.truncate(32);
-        HashWithTD { hash: data, td }
+impl ElemCodec for HashWithTDCodec {
+    type Item = HashWithTD;
+    fn encode(item: &Self::Item, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&item.hash);
+        buf.extend_from_slice(&item.td.to_le_bytes());
+    }
+    fn decode(bytes: &[u8]) -> Result<(Self::Item, usize)> {
+        let (hash, len) = FixedBytesCodec::<32>::decode(bytes)?;
+        let (td, _) = FixedBytesCodec::<8>::decode(&bytes[len..])?;
+        let item = HashWithTD {
+            hash: hash.to_vec().into(),
+            td: u64::from_le_bytes(td),
+        };
+        Ok((item, Self::SIZE))
     }
 }
 
+impl FixedSizeElemCodec for HashWithTDCodec {
+    const SIZE: usize = 40;
+}
+
 impl Debug for HashWithTD {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
@@ -68,6 +81,26 @@ impl Debug for HashWithTD {
     }
 }
 
+impl Weighted for HashWithTD {
+    type Weight = u64;
+    fn weight(&self) -> u64 {
+        self.td
+    }
+}
+
+struct Blake2bOracle;
+
+impl RandomOracle<HashWithTD> for Blake2bOracle {
+    fn random(root: &HashWithTD, index: u32) -> u64 {
+        let mut hasher = new_blake2b();
+        let mut hash = [0u8; 32];
+        hasher.update(&HashWithTDCodec::encode_to_vec(root));
+        hasher.update(&index.to_le_bytes());
+        hasher.finalize(&mut hash);
+        u64::from_le_bytes(hash[..8].try_into().unwrap())
+    }
+}
+
 struct MergeHashWithTD;
 
 impl Merge for MergeHashWithTD {
@@ -75,8 +108,8 @@ impl Merge for MergeHashWithTD {
     fn merge(lhs: &Self::Item, rhs: &Self::Item) -> Self::Item {
         let mut hasher = new_blake2b();
         let mut hash = [0u8; 32];
-        hasher.update(&lhs.serialize());
-        hasher.update(&rhs.serialize());
+        hasher.update(&HashWithTDCodec::encode_to_vec(lhs));
+        hasher.update(&HashWithTDCodec::encode_to_vec(rhs));
         hasher.finalize(&mut hash);
         let td = lhs.td + rhs.td;
         HashWithTD {
@@ -125,7 +158,7 @@ impl Prover {
                 number: i,
                 parent_hash: previous.hash.clone(),
                 difficulty: i,
-                chain_root: mmr.get_root()?.serialize(),
+                chain_root: HashWithTDCodec::encode_to_vec(&mmr.get_root()?).into(),
             };
             previous = HashWithTD {
                 hash: block.hash(),
@@ -151,9 +184,9 @@ impl Prover {
         assert!(number < later_number);
         let pos = self.positions[number as usize];
         let later_pos = self.positions[later_number as usize];
-        let mmr = MMR::new(later_pos, &self.store);
+        let mmr: MMR<_, MergeHashWithTD, _> = MMR::new(later_pos, &self.store);
         assert_eq!(
-            mmr.get_root()?.serialize(),
+            HashWithTDCodec::encode_to_vec(&mmr.get_root()?),
             self.headers[later_number as usize].0.chain_root
         );
         mmr.gen_proof(vec![pos])
@@ -162,6 +195,19 @@ impl Prover {
     fn get_pos(&self, number: u64) -> u64 {
         self.positions[number as usize]
     }
+
+    fn get_elem(&self, number: u64) -> HashWithTD {
+        let (header, td) = self.get_header(number);
+        HashWithTD {
+            hash: header.hash(),
+            td,
+        }
+    }
+
+    fn get_mmr(&self) -> MMR<HashWithTD, MergeHashWithTD, &MemStore<HashWithTD>> {
+        let mmr_size = leaf_index_to_mmr_size(self.headers.len() as u64 - 1);
+        MMR::new(mmr_size, &self.store)
+    }
 }
 
 #[test]
@@ -181,7 +227,7 @@ fn test_insert_header() {
     };
     let root = {
         let (later_header, _later_td) = prover.get_header(h2);
-        HashWithTD::deserialize(later_header.chain_root)
+        HashWithTDCodec::decode_all(&later_header.chain_root).expect("decode root")
     };
     // gen proof,  blocks are in the same chain
     let proof = prover.gen_proof(h1, h2).expect("gen proof");
@@ -191,3 +237,245 @@ fn test_insert_header() {
     let result = proof.verify(root, vec![(pos, prove_elem)]).expect("verify");
     assert!(result);
 }
+
+fn test_left_weight(count: u64) {
+    let mut prover = Prover::new();
+    prover.gen_blocks(count).expect("gen blocks");
+    let mmr = prover.get_mmr();
+    let root = mmr.get_root().expect("get root");
+    let mut left_weight = 0;
+    for number in 0..=count {
+        let proof = mmr.gen_left_weight_proof(number).expect("gen proof");
+        assert_eq!(proof.left_weight(), Ok(left_weight));
+        let elem = prover.get_elem(number);
+        assert!(proof
+            .verify_left_weight(root.clone(), elem.clone(), left_weight)
+            .unwrap());
+        assert!(!proof
+            .verify_left_weight(root.clone(), elem.clone(), left_weight + 1)
+            .unwrap());
+        left_weight += elem.td;
+    }
+}
+
+fn test_find_leaf_by_weight(count: u64, weight: u64) {
+    let mut prover = Prover::new();
+    prover.gen_blocks(count).expect("gen blocks");
+    let mmr = prover.get_mmr();
+    let root = mmr.get_root().expect("get root");
+    // block `i` has difficulty `i`, so leaves before block `n` weigh n * (n - 1) / 2
+    let number = (0..=count)
+        .take_while(|n| n * (n + 1) / 2 <= weight)
+        .last()
+        .map_or(0, |n| n + 1);
+    let proof = mmr.find_leaf_by_weight(weight).expect("find leaf");
+    assert_eq!(proof.leaf_index(), number);
+    assert_eq!(proof.left_weight(), Ok(number * (number - 1) / 2));
+    let elem = prover.get_elem(number);
+    assert!(proof
+        .verify_weight(root.clone(), elem.clone(), weight)
+        .unwrap());
+    // the weight is out of the interval of other leaves
+    let prev_elem = prover.get_elem(number - 1);
+    assert!(!proof.verify_weight(root, prev_elem, weight).unwrap());
+}
+
+#[test]
+fn test_weighted_headers() {
+    test_left_weight(0);
+    test_left_weight(30);
+    for weight in 0..(30 * 31 / 2) {
+        test_find_leaf_by_weight(30, weight);
+    }
+}
+
+#[test]
+fn test_left_weight_out_of_mmr() {
+    let mut prover = Prover::new();
+    prover.gen_blocks(30).expect("gen blocks");
+    let mmr = prover.get_mmr();
+    assert_eq!(
+        mmr.gen_left_weight_proof(31).map(|_| ()),
+        Err(Error::GenProofForInvalidLeaves)
+    );
+    // a corrupted leaf index out of the MMR of proof
+    let root = mmr.get_root().expect("get root");
+    let proof = mmr.gen_left_weight_proof(5).expect("gen proof");
+    for leaf_index in [31, u64::MAX] {
+        let proof = WeightProof::new(
+            leaf_index,
+            MerkleProof::<_, MergeHashWithTD>::new(
+                proof.proof().mmr_size(),
+                proof.proof().proof_items().to_vec(),
+            ),
+        );
+        assert_eq!(proof.left_weight(), Err(Error::CorruptedProof));
+        assert_eq!(
+            proof.verify_left_weight(root.clone(), prover.get_elem(5), 10),
+            Err(Error::CorruptedProof)
+        );
+    }
+}
+
+#[test]
+fn test_find_leaf_beyond_total_weight() {
+    let mut prover = Prover::new();
+    prover.gen_blocks(30).expect("gen blocks");
+    let mmr = prover.get_mmr();
+    assert_eq!(
+        mmr.find_leaf_by_weight(30 * 31 / 2).map(|_| ()),
+        Err(Error::WeightOutOfRange)
+    );
+}
+
+proptest! {
+    #[test]
+    fn test_random_find_leaf_by_weight(count in 10u64..300u64) {
+        let weight = thread_rng().gen_range(0, count * (count + 1) / 2);
+        test_find_leaf_by_weight(count, weight);
+    }
+}
+
+fn test_sample_proof<D: SampleDistribution<u64>>(count: u64, distribution: D, sample_count: u32) {
+    let mut prover = Prover::new();
+    prover.gen_blocks(count).expect("gen blocks");
+    let mmr = prover.get_mmr();
+    let root = mmr.get_root().expect("get root");
+    let proof = mmr
+        .gen_sample_proof::<Blake2bOracle, _>(&distribution, sample_count)
+        .expect("gen sample proof");
+    let leaf_indices = proof.leaf_indices().to_vec();
+    // every target is opened
+    for index in 0..sample_count {
+        let weight = distribution.sample(Blake2bOracle::random(&root, index), root.td);
+        let leaf_index = mmr.find_leaf_by_weight(weight).unwrap().leaf_index();
+        assert!(leaf_indices.contains(&leaf_index));
+    }
+    let get_leaves = |leaf_indices: &[u64]| -> Vec<HashWithTD> {
+        leaf_indices.iter().map(|&n| prover.get_elem(n)).collect()
+    };
+    assert!(proof
+        .verify::<Blake2bOracle, _>(
+            root.clone(),
+            &distribution,
+            sample_count,
+            get_leaves(&leaf_indices)
+        )
+        .unwrap());
+
+    // open other leaves
+    let other_indices: Vec<u64> = leaf_indices
+        .iter()
+        .map(|&n| if n == count { n - 1 } else { n + 1 })
+        .filter(|n| !leaf_indices.contains(n))
+        .take(1)
+        .chain(leaf_indices.iter().cloned().skip(1))
+        .collect::<std::collections::BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let other_proof = SampleProof::new(
+        other_indices.clone(),
+        mmr.gen_proof_by_leaf_indices(other_indices.clone())
+            .unwrap(),
+    );
+    assert!(!other_proof
+        .verify::<Blake2bOracle, _>(
+            root.clone(),
+            &distribution,
+            sample_count,
+            get_leaves(&other_indices)
+        )
+        .unwrap());
+    // open an extra leaf
+    let extra_indices: Vec<u64> = (0..=count)
+        .filter(|n| !leaf_indices.contains(n))
+        .take(1)
+        .chain(leaf_indices.iter().cloned())
+        .collect::<std::collections::BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let extra_proof = SampleProof::new(
+        extra_indices.clone(),
+        mmr.gen_proof_by_leaf_indices(extra_indices.clone())
+            .unwrap(),
+    );
+    assert!(!extra_proof
+        .verify::<Blake2bOracle, _>(
+            root,
+            &distribution,
+            sample_count,
+            get_leaves(&extra_indices)
+        )
+        .unwrap());
+}
+
+#[test]
+fn test_sample_headers() {
+    test_sample_proof(100, UniformDistribution, 20);
+    test_sample_proof(100, FlyClientDistribution::new(0.01).unwrap(), 20);
+    test_sample_proof(1000, FlyClientDistribution::new(0.5).unwrap(), 50);
+}
+
+#[test]
+fn test_sample_proof_out_of_mmr() {
+    let mut prover = Prover::new();
+    prover.gen_blocks(30).expect("gen blocks");
+    let mmr = prover.get_mmr();
+    let root = mmr.get_root().expect("get root");
+    let proof = mmr.gen_proof_by_leaf_indices(vec![5]).expect("gen proof");
+    for leaf_index in [31, u64::MAX] {
+        let proof = SampleProof::new(
+            vec![5, leaf_index],
+            MerkleProof::<_, MergeHashWithTD>::new(proof.mmr_size(), proof.proof_items().to_vec()),
+        );
+        assert_eq!(
+            proof.verify::<Blake2bOracle, _>(
+                root.clone(),
+                &UniformDistribution,
+                2,
+                vec![prover.get_elem(5), prover.get_elem(6)]
+            ),
+            Err(Error::CorruptedProof)
+        );
+    }
+}
+
+// an extra sample may hit a leaf which is opened already
+#[test]
+fn test_sample_proof_with_repeated_leaves() {
+    test_sample_proof(31, UniformDistribution, 22);
+}
+
+#[test]
+fn test_flyclient_distribution() {
+    let distribution = FlyClientDistribution::new(0.01).unwrap();
+    assert_eq!(distribution.sample(0, 1000), 0);
+    assert_eq!(distribution.sample(u64::MAX, 1000), 990);
+    // recent weight is sampled more often
+    assert!(distribution.sample(u64::MAX / 2, 1000) > 500);
+
+    // the suffix of 10 leaves in 1000 leaves of the same weight
+    let delta = FlyClientDistribution::vd_calculate_delta(10, 1000).unwrap();
+    assert_eq!(FlyClientDistribution::new(delta), Ok(distribution));
+    assert_eq!(FlyClientDistribution::vd_calculate_delta(0, 0), Ok(1.0));
+    assert_eq!(FlyClientDistribution::vd_calculate_delta(20, 10), Ok(1.0));
+}
+
+#[test]
+fn test_flyclient_invalid_delta() {
+    assert_eq!(
+        FlyClientDistribution::vd_calculate_delta(0, 1000),
+        Err(Error::InvalidDelta)
+    );
+    for delta in [0.0, -0.5, 1.5, f64::NAN] {
+        assert_eq!(FlyClientDistribution::new(delta), Err(Error::InvalidDelta));
+    }
+    assert!(FlyClientDistribution::new(1.0).is_ok());
+}
+
+proptest! {
+    #[test]
+    fn test_random_sample_proof(count in 10u64..300u64, sample_count in 1u32..30u32) {
+        test_sample_proof(count, UniformDistribution, sample_count);
+    }
+}
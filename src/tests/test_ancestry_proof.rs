@@ -0,0 +1,98 @@
+use super::{MergeNumberHash, NumberHash};
+use crate::{leaf_index_to_mmr_size, util::MemStore, Error, MMR};
+use proptest::prelude::*;
+
+fn test_ancestry_proof(prev_count: u32, count: u32) {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    let mut prev_root = None;
+    for i in 0u32..count {
+        mmr.push(NumberHash::from(i)).unwrap();
+        if i + 1 == prev_count {
+            prev_root = Some(mmr.get_root().expect("get root"));
+        }
+    }
+    let prev_root = prev_root.expect("prev root");
+    let root = mmr.get_root().expect("get root");
+    let prev_mmr_size = leaf_index_to_mmr_size(u64::from(prev_count) - 1);
+    let proof = mmr
+        .gen_ancestry_proof(prev_mmr_size)
+        .expect("gen ancestry proof");
+    assert_eq!(proof.prev_mmr_size(), prev_mmr_size);
+    assert!(proof
+        .verify_ancestor(root.clone(), prev_root.clone())
+        .unwrap());
+    // a different previous root must be rejected
+    assert!(!proof
+        .verify_ancestor(root.clone(), NumberHash::from(count))
+        .unwrap());
+    // a different current root must be rejected
+    assert!(!proof
+        .verify_ancestor(NumberHash::from(count), prev_root)
+        .unwrap());
+}
+
+#[test]
+fn test_ancestry_proof_of_itself() {
+    test_ancestry_proof(11, 11);
+    test_ancestry_proof(1, 1);
+}
+
+#[test]
+fn test_ancestry_proofs() {
+    test_ancestry_proof(1, 2);
+    test_ancestry_proof(2, 11);
+    test_ancestry_proof(7, 8);
+    test_ancestry_proof(10, 11);
+    test_ancestry_proof(11, 100);
+}
+
+#[test]
+fn test_ancestry_proof_from_forked_mmr() {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    let forked_store = MemStore::default();
+    let mut forked_mmr = MMR::<_, MergeNumberHash, _>::new(0, &forked_store);
+    for i in 0u32..10 {
+        mmr.push(NumberHash::from(i)).unwrap();
+        forked_mmr.push(NumberHash::from(i + 100)).unwrap();
+    }
+    let forked_root = forked_mmr.get_root().expect("get root");
+    let prev_mmr_size = mmr.mmr_size();
+    for i in 10u32..20 {
+        mmr.push(NumberHash::from(i)).unwrap();
+    }
+    let root = mmr.get_root().expect("get root");
+    let proof = mmr
+        .gen_ancestry_proof(prev_mmr_size)
+        .expect("gen ancestry proof");
+    assert!(!proof.verify_ancestor(root, forked_root).unwrap());
+}
+
+#[test]
+fn test_ancestry_proof_invalid_size() {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    for i in 0u32..11 {
+        mmr.push(NumberHash::from(i)).unwrap();
+    }
+    assert_eq!(
+        mmr.gen_ancestry_proof(0).map(|_| ()),
+        Err(Error::InvalidMMRSize)
+    );
+    assert_eq!(
+        mmr.gen_ancestry_proof(2).map(|_| ()),
+        Err(Error::InvalidMMRSize)
+    );
+    assert_eq!(
+        mmr.gen_ancestry_proof(mmr.mmr_size() + 1).map(|_| ()),
+        Err(Error::InvalidMMRSize)
+    );
+}
+
+proptest! {
+    #[test]
+    fn test_random_ancestry_proof((prev_count, count) in (1u32..500u32).prop_flat_map(|count| (1..=count, Just(count)))) {
+        test_ancestry_proof(prev_count, count);
+    }
+}
@@ -0,0 +1,165 @@
+use super::{MergeNumberHash, NumberHash};
+use crate::{util::MemStore, AsyncMMR, AsyncMMRStore, Error, Result, MMR};
+use core::future::Future;
+use core::pin::pin;
+use core::task::{Context, Poll, Waker};
+use proptest::prelude::*;
+use rand::{seq::SliceRandom, thread_rng};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+// futures of the in-memory store are always ready, so polling in a loop is enough
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+fn test_async_mmr(count: u32, proof_elems: Vec<u32>) {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    let async_store = MemStore::default();
+    let mut async_mmr = AsyncMMR::<_, MergeNumberHash, _>::new(0, &async_store);
+    let positions: Vec<u64> = (0u32..count)
+        .map(|i| {
+            let pos = block_on(async_mmr.push(NumberHash::from(i))).unwrap();
+            assert_eq!(mmr.push(NumberHash::from(i)).unwrap(), pos);
+            pos
+        })
+        .collect();
+    let root = block_on(async_mmr.get_root()).expect("get root");
+    assert_eq!(root, mmr.get_root().unwrap());
+    let pos_list: Vec<u64> = proof_elems.iter().map(|&i| positions[i as usize]).collect();
+    let proof = block_on(async_mmr.gen_proof(pos_list.clone())).expect("gen proof");
+    assert_eq!(
+        proof.proof_items(),
+        mmr.gen_proof(pos_list).unwrap().proof_items()
+    );
+    assert!(proof
+        .verify(
+            root.clone(),
+            proof_elems
+                .iter()
+                .map(|&i| (positions[i as usize], NumberHash::from(i)))
+                .collect()
+        )
+        .unwrap());
+
+    // proofs are generated from the store after commit
+    let mmr_size = async_mmr.mmr_size();
+    block_on(async_mmr.commit()).expect("commit changes");
+    let async_mmr = AsyncMMR::<_, MergeNumberHash, _>::new(mmr_size, &async_store);
+    assert_eq!(block_on(async_mmr.get_root()), Ok(root));
+    assert_eq!(
+        block_on(async_mmr.gen_proof(vec![positions[proof_elems[0] as usize]]))
+            .unwrap()
+            .mmr_size(),
+        mmr_size
+    );
+}
+
+#[test]
+fn test_async_mmr_3_peaks() {
+    test_async_mmr(11, vec![5]);
+    test_async_mmr(11, vec![0, 4, 10]);
+}
+
+#[test]
+fn test_async_mmr_1_leaf() {
+    test_async_mmr(1, vec![0]);
+}
+
+#[test]
+fn test_async_mmr_empty() {
+    let store = MemStore::<NumberHash>::default();
+    let mmr = AsyncMMR::<_, MergeNumberHash, _>::new(0, &store);
+    assert_eq!(block_on(mmr.get_root()), Err(Error::GetRootOnEmpty));
+    assert_eq!(
+        block_on(mmr.gen_proof(Vec::new())).map(|_| ()),
+        Err(Error::GenProofForInvalidLeaves)
+    );
+}
+
+/// Thread-safe store, the futures borrow the store
+#[derive(Default)]
+struct SyncMemStore(Mutex<BTreeMap<u64, NumberHash>>);
+
+impl AsyncMMRStore<NumberHash> for &SyncMemStore {
+    async fn get_elem(&self, pos: u64) -> Result<Option<NumberHash>> {
+        Ok(self.0.lock().unwrap().get(&pos).cloned())
+    }
+
+    async fn append(&mut self, pos: u64, elems: Vec<NumberHash>) -> Result<()> {
+        let mut store = self.0.lock().unwrap();
+        for (i, elem) in elems.into_iter().enumerate() {
+            store.insert(pos + i as u64, elem);
+        }
+        Ok(())
+    }
+}
+
+// futures are `Send` for any `Send` and `Sync` store, so they can be polled on other threads
+fn push_on_threads<S: AsyncMMRStore<NumberHash> + Send + Sync>(
+    mmr: &mut AsyncMMR<NumberHash, MergeNumberHash, S>,
+    count: u32,
+) {
+    for i in 0..count {
+        let future = mmr.push(NumberHash::from(i));
+        std::thread::scope(|scope| {
+            scope
+                .spawn(move || block_on(future))
+                .join()
+                .unwrap()
+                .unwrap();
+        });
+    }
+}
+
+#[test]
+fn test_async_mmr_send() {
+    let store = SyncMemStore::default();
+    let mut mmr = AsyncMMR::<_, MergeNumberHash, _>::new(0, &store);
+    push_on_threads(&mut mmr, 11);
+    let mmr_size = mmr.mmr_size();
+    let root = std::thread::scope(|scope| {
+        let future = mmr.get_root();
+        scope.spawn(move || block_on(future)).join().unwrap()
+    })
+    .unwrap();
+    let proof = std::thread::scope(|scope| {
+        let future = mmr.gen_proof(vec![0]);
+        scope.spawn(move || block_on(future)).join().unwrap()
+    })
+    .unwrap();
+    assert!(proof
+        .verify(root.clone(), vec![(0, NumberHash::from(0))])
+        .unwrap());
+    std::thread::scope(|scope| {
+        let future = mmr.commit();
+        scope.spawn(move || block_on(future)).join().unwrap()
+    })
+    .unwrap();
+    assert_eq!(store.0.lock().unwrap().len() as u64, mmr_size);
+
+    let mem_store = MemStore::default();
+    let mut mem_mmr = MMR::<_, MergeNumberHash, _>::new(0, &mem_store);
+    (0u32..11).for_each(|i| {
+        mem_mmr.push(NumberHash::from(i)).unwrap();
+    });
+    assert_eq!(mem_mmr.get_root(), Ok(root));
+}
+
+proptest! {
+    #[test]
+    fn test_random_async_mmr(count in 10u32..500u32) {
+        let mut leaves: Vec<u32> = (0..count).collect();
+        let mut rng = thread_rng();
+        leaves.shuffle(&mut rng);
+        leaves.truncate(rng.gen_range(1, count - 1) as usize);
+        test_async_mmr(count, leaves);
+    }
+}
@@ -0,0 +1,126 @@
+use super::{MergeNumberHash, NumberHash};
+use crate::{
+    helper::get_peaks, util::MemStore, CommitMMRSize, Error, LeftToRightBagging, MMRSizeBagging,
+    MMRStore, Merge, PeakBagging, PeakListBagging, PeaksAccumulator, Result, RightToLeftBagging,
+    MMR,
+};
+use proptest::prelude::*;
+
+struct CommitSizeNumberHash;
+
+impl CommitMMRSize for CommitSizeNumberHash {
+    type Item = NumberHash;
+    fn commit_mmr_size(root: &NumberHash, mmr_size: u64) -> Result<NumberHash> {
+        Ok(MergeNumberHash::merge(
+            &NumberHash::from(mmr_size as u32),
+            root,
+        ))
+    }
+}
+
+// push elems and check root and proofs of every leaf with bagging strategy `B`
+fn test_bagging<B: PeakBagging<NumberHash>>(count: u32) -> B::Root
+where
+    B::Root: PartialEq + core::fmt::Debug,
+{
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _, B>::new(0, &store);
+    let mut accumulator = PeaksAccumulator::<_, MergeNumberHash, B>::default();
+    let positions: Vec<u64> = (0u32..count)
+        .map(|i| {
+            accumulator.push(NumberHash::from(i)).unwrap();
+            mmr.push(NumberHash::from(i)).unwrap()
+        })
+        .collect();
+    let root = mmr.get_root().expect("get root");
+    assert_eq!(accumulator.get_root().expect("get root"), root);
+    for (i, pos) in positions.iter().enumerate() {
+        let proof = mmr.gen_proof(vec![*pos]).expect("gen proof");
+        assert!(proof
+            .verify(
+                mmr.get_root().unwrap(),
+                vec![(*pos, NumberHash::from(i as u32))]
+            )
+            .unwrap());
+    }
+    root
+}
+
+fn peaks(count: u32) -> Vec<NumberHash> {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    (0u32..count).for_each(|i| {
+        mmr.push(NumberHash::from(i)).unwrap();
+    });
+    let mmr_size = mmr.mmr_size();
+    mmr.commit().expect("commit changes");
+    get_peaks(mmr_size)
+        .into_iter()
+        .map(|pos| (&store).get_elem(pos).unwrap().unwrap())
+        .collect()
+}
+
+#[test]
+fn test_default_bagging() {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    (0u32..11).for_each(|i| {
+        mmr.push(NumberHash::from(i)).unwrap();
+    });
+    assert_eq!(
+        test_bagging::<RightToLeftBagging>(11),
+        mmr.get_root().unwrap()
+    );
+}
+
+#[test]
+fn test_left_to_right_bagging() {
+    let peaks = peaks(11);
+    let expected = peaks[1..].iter().fold(peaks[0].clone(), |root, peak| {
+        MergeNumberHash::merge(&root, peak)
+    });
+    assert_eq!(test_bagging::<LeftToRightBagging>(11), expected);
+}
+
+#[test]
+fn test_mmr_size_bagging() {
+    let root = test_bagging::<RightToLeftBagging>(11);
+    let mmr_size = crate::leaf_count_to_mmr_size(11);
+    assert_eq!(
+        test_bagging::<MMRSizeBagging<CommitSizeNumberHash>>(11),
+        MergeNumberHash::merge(&NumberHash::from(mmr_size as u32), &root)
+    );
+}
+
+#[test]
+fn test_peak_list_bagging() {
+    assert_eq!(test_bagging::<PeakListBagging>(11), peaks(11));
+    assert_eq!(test_bagging::<PeakListBagging>(1), peaks(1));
+}
+
+#[test]
+fn test_peak_list_bagging_missing_peaks() {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _, PeakListBagging>::new(0, &store);
+    (0u32..11).for_each(|i| {
+        mmr.push(NumberHash::from(i)).unwrap();
+    });
+    let proof = mmr.gen_proof(vec![0]).unwrap();
+    let mut items = proof.proof_items().to_vec();
+    items.pop();
+    let proof =
+        crate::MerkleProof::<_, MergeNumberHash, PeakListBagging>::new(proof.mmr_size(), items);
+    assert_eq!(
+        proof.calculate_root(vec![(0, NumberHash::from(0))]),
+        Err(Error::CorruptedProof)
+    );
+}
+
+proptest! {
+    #[test]
+    fn test_random_bagging(count in 1u32..200u32) {
+        test_bagging::<LeftToRightBagging>(count);
+        test_bagging::<MMRSizeBagging<CommitSizeNumberHash>>(count);
+        test_bagging::<PeakListBagging>(count);
+    }
+}
@@ -0,0 +1,119 @@
+use super::{MergeNumberHash, NumberHash};
+use crate::{util::MemStore, CachedStore, MMRStore, MMR};
+
+fn new_store(count: u32) -> (MemStore<NumberHash>, u64) {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    (0u32..count).for_each(|i| {
+        mmr.push(NumberHash::from(i)).unwrap();
+    });
+    let mmr_size = mmr.mmr_size();
+    mmr.commit().expect("commit changes");
+    (store, mmr_size)
+}
+
+#[test]
+fn test_cached_store() {
+    let (store, mmr_size) = new_store(11);
+    let cached = CachedStore::new(&store, 8, 2);
+    let mmr = MMR::<_, MergeNumberHash, _>::new(mmr_size, &cached);
+    let root = mmr.get_root().unwrap();
+    // 3 peaks are missed then cached
+    assert_eq!((cached.hits(), cached.misses()), (0, 3));
+    assert_eq!(mmr.get_root().unwrap(), root);
+    assert_eq!((cached.hits(), cached.misses()), (3, 3));
+
+    let proof = mmr.gen_proof(vec![0]).unwrap();
+    let misses = cached.misses();
+    assert_eq!(
+        mmr.gen_proof(vec![0]).unwrap().proof_items(),
+        proof.proof_items()
+    );
+    assert_eq!(cached.misses(), misses);
+    assert!(proof.verify(root, vec![(0, NumberHash::from(0))]).unwrap());
+}
+
+#[test]
+fn test_cached_store_evict() {
+    let (store, _mmr_size) = new_store(11);
+    let cached = CachedStore::new(&store, 2, 2);
+    // leaves are not pinned, only 2 of them are cached
+    for pos in &[0u64, 1, 3] {
+        (&cached).get_elem(*pos).unwrap();
+    }
+    assert_eq!(cached.misses(), 3);
+    (&cached).get_elem(3).unwrap();
+    (&cached).get_elem(1).unwrap();
+    assert_eq!((cached.hits(), cached.misses()), (2, 3));
+    // pos 0 is the least recently used one
+    (&cached).get_elem(0).unwrap();
+    assert_eq!((cached.hits(), cached.misses()), (2, 4));
+
+    // pinned nodes are never evicted
+    (&cached).get_elem(6).unwrap();
+    for pos in &[7u64, 8, 10, 11] {
+        (&cached).get_elem(*pos).unwrap();
+    }
+    let misses = cached.misses();
+    (&cached).get_elem(6).unwrap();
+    assert_eq!(cached.misses(), misses);
+}
+
+#[test]
+fn test_cached_store_truncate() {
+    let (store, mmr_size) = new_store(11);
+    let cached = CachedStore::new(&store, 64, 2);
+    let mmr = MMR::<_, MergeNumberHash, _>::new(mmr_size, &cached);
+    mmr.get_root().unwrap();
+
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(mmr_size, &cached);
+    mmr.rewind(7).unwrap();
+    mmr.commit().expect("commit changes");
+    assert_eq!((&cached).get_elem(7).unwrap(), None);
+
+    // rebuild with other elems, stale elems must not be served
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(7, &cached);
+    (100u32..107).for_each(|i| {
+        mmr.push(NumberHash::from(i)).unwrap();
+    });
+    mmr.commit().expect("commit changes");
+    let mmr = MMR::<_, MergeNumberHash, _>::new(mmr_size, &store);
+    let mmr_cached = MMR::<_, MergeNumberHash, _>::new(mmr_size, &cached);
+    assert_eq!(mmr_cached.get_root(), mmr.get_root());
+    assert_eq!(
+        (&cached).get_elem(7).unwrap(),
+        (&store).get_elem(7).unwrap()
+    );
+}
+
+#[test]
+fn test_cached_store_bounded() {
+    let (store, mmr_size) = new_store(1000);
+    // pin every node, or nodes above leaves
+    for &pin_height in &[0u32, 1] {
+        let cached = CachedStore::new(&store, 16, pin_height);
+        for pos in 0..mmr_size {
+            (&cached).get_elem(pos).unwrap();
+            assert!(cached.len() <= 16);
+        }
+        assert_eq!(cached.len(), 16);
+        // the first pinned nodes are kept
+        let misses = cached.misses();
+        (&cached).get_elem(2).unwrap();
+        assert_eq!(cached.misses(), misses);
+    }
+
+    // pinned nodes take the room of leaves
+    let cached = CachedStore::new(&store, 4, 1);
+    for &pos in &[0u64, 1, 3, 4] {
+        (&cached).get_elem(pos).unwrap();
+    }
+    (&cached).get_elem(2).unwrap();
+    (&cached).get_elem(5).unwrap();
+    assert_eq!(cached.len(), 4);
+    let misses = cached.misses();
+    for &pos in &[2u64, 5, 3, 4] {
+        (&cached).get_elem(pos).unwrap();
+    }
+    assert_eq!(cached.misses(), misses);
+}
@@ -0,0 +1,137 @@
+use super::{MergeNumberHash, NumberHash, NumberHashCodec};
+use crate::{
+    util::MemStore, BytesCodec, CodecError, ElemCodec, Error, FixedBytesCodec, FixedSizeElemCodec,
+    MerkleProof, MERKLE_PROOF_ENCODING_VERSION, MMR,
+};
+use proptest::prelude::*;
+
+type NumberHashProof = MerkleProof<NumberHash, MergeNumberHash>;
+
+fn gen_proof(count: u32, elem: u32) -> (NumberHashProof, NumberHash, u64) {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    let positions: Vec<u64> = (0u32..count)
+        .map(|i| mmr.push(NumberHash::from(i)).unwrap())
+        .collect();
+    let root = mmr.get_root().expect("get root");
+    let pos = positions[elem as usize];
+    let proof = mmr.gen_proof(vec![pos]).expect("gen proof");
+    (proof, root, pos)
+}
+
+fn test_encode_decode(count: u32, elem: u32) {
+    let (proof, root, pos) = gen_proof(count, elem);
+    let bytes = proof.encode::<NumberHashCodec>();
+    assert_eq!(bytes[0], MERKLE_PROOF_ENCODING_VERSION);
+    assert_eq!(bytes.len(), 13 + 32 * proof.proof_items().len());
+    let decoded = NumberHashProof::decode::<NumberHashCodec>(&bytes).expect("decode");
+    assert_eq!(decoded.mmr_size(), proof.mmr_size());
+    assert_eq!(decoded.proof_items(), proof.proof_items());
+    assert!(decoded
+        .verify(root, vec![(pos, NumberHash::from(elem))])
+        .unwrap());
+}
+
+#[test]
+fn test_encode_decode_proof() {
+    test_encode_decode(11, 5);
+    test_encode_decode(1, 0);
+}
+
+#[test]
+fn test_decode_invalid_input() {
+    let (proof, _root, _pos) = gen_proof(11, 5);
+    let bytes = proof.encode::<NumberHashCodec>();
+    for len in 0..bytes.len() {
+        assert_eq!(
+            NumberHashProof::decode::<NumberHashCodec>(&bytes[..len]).map(|_| ()),
+            Err(Error::Codec(CodecError::Truncated))
+        );
+    }
+
+    let mut oversized = bytes.clone();
+    oversized.push(0);
+    assert_eq!(
+        NumberHashProof::decode::<NumberHashCodec>(&oversized).map(|_| ()),
+        Err(Error::Codec(CodecError::Oversized))
+    );
+
+    // declare more items than nodes of the MMR
+    let mut oversized = bytes.clone();
+    oversized[9..13].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert_eq!(
+        NumberHashProof::decode::<NumberHashCodec>(&oversized).map(|_| ()),
+        Err(Error::Codec(CodecError::Oversized))
+    );
+
+    let mut unknown_version = bytes;
+    unknown_version[0] = MERKLE_PROOF_ENCODING_VERSION + 1;
+    assert_eq!(
+        NumberHashProof::decode::<NumberHashCodec>(&unknown_version).map(|_| ()),
+        Err(Error::Codec(CodecError::UnsupportedVersion(
+            MERKLE_PROOF_ENCODING_VERSION + 1
+        )))
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_proof() {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberU64, _>::new(0, &store);
+    let positions: Vec<u64> = (0u64..11).map(|i| mmr.push(i).unwrap()).collect();
+    let root = mmr.get_root().expect("get root");
+    let proof = mmr.gen_proof(vec![positions[5]]).expect("gen proof");
+    let json = serde_json::to_string(&proof).expect("serialize");
+    let decoded: MerkleProof<u64, MergeNumberU64> =
+        serde_json::from_str(&json).expect("deserialize");
+    assert_eq!(decoded.mmr_size(), proof.mmr_size());
+    assert_eq!(decoded.proof_items(), proof.proof_items());
+    assert!(decoded.verify(root, vec![(positions[5], 5)]).unwrap());
+}
+
+#[test]
+fn test_elem_codecs() {
+    let hash = [7u8; 32];
+    let bytes = FixedBytesCodec::<32>::encode_to_vec(&hash);
+    assert_eq!(bytes.len(), FixedBytesCodec::<32>::SIZE);
+    assert_eq!(FixedBytesCodec::<32>::decode_all(&bytes), Ok(hash));
+    assert_eq!(
+        FixedBytesCodec::<32>::decode(&bytes[..31]).map(|_| ()),
+        Err(CodecError::Truncated.into())
+    );
+
+    let item = vec![1u8, 2, 3];
+    let mut bytes = BytesCodec::encode_to_vec(&item);
+    assert_eq!(bytes, vec![3, 0, 0, 0, 1, 2, 3]);
+    assert_eq!(BytesCodec::decode_all(&bytes), Ok(item.clone()));
+    // items are self-delimiting
+    BytesCodec::encode(&Vec::new(), &mut bytes);
+    assert_eq!(BytesCodec::decode(&bytes), Ok((item, 7)));
+    assert_eq!(
+        BytesCodec::decode_all(&bytes),
+        Err(CodecError::Oversized.into())
+    );
+    assert_eq!(
+        BytesCodec::decode(&bytes[..6]),
+        Err(CodecError::Truncated.into())
+    );
+}
+
+#[cfg(feature = "serde")]
+struct MergeNumberU64;
+
+#[cfg(feature = "serde")]
+impl crate::Merge for MergeNumberU64 {
+    type Item = u64;
+    fn merge(lhs: &Self::Item, rhs: &Self::Item) -> Self::Item {
+        lhs.wrapping_mul(31).wrapping_add(*rhs).rotate_left(7)
+    }
+}
+
+proptest! {
+    #[test]
+    fn test_random_encode_decode((count, elem) in (1u32..500u32).prop_flat_map(|count| (Just(count), 0..count))) {
+        test_encode_decode(count, elem);
+    }
+}
@@ -0,0 +1,244 @@
+use super::{MergeNumberHash, NumberHash, NumberHashCodec};
+use crate::{util::MemStore, Error, FileStore, MMRStore, MMR};
+use std::fs;
+use std::path::PathBuf;
+
+type NumberHashStore = FileStore<NumberHash, NumberHashCodec>;
+
+// a fresh directory for each test
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("mmr-file-store-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn test_file_store() {
+    let dir = temp_dir("mmr");
+    let store = NumberHashStore::open(&dir).unwrap();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    let mem_store = MemStore::default();
+    let mut mem_mmr = MMR::<_, MergeNumberHash, _>::new(0, &mem_store);
+    let positions: Vec<u64> = (0u32..11)
+        .map(|i| {
+            mem_mmr.push(NumberHash::from(i)).unwrap();
+            mmr.push(NumberHash::from(i)).unwrap()
+        })
+        .collect();
+    let root = mmr.get_root().unwrap();
+    assert_eq!(root, mem_mmr.get_root().unwrap());
+    let mmr_size = mmr.mmr_size();
+    mmr.commit().expect("commit changes");
+    assert_eq!(store.mmr_size(), mmr_size);
+
+    // reopen
+    drop(store);
+    let store = NumberHashStore::open(&dir).unwrap();
+    assert_eq!(store.mmr_size(), mmr_size);
+    assert_eq!((&store).get_elem(mmr_size).unwrap(), None);
+    let mmr = MMR::<_, MergeNumberHash, _>::new(mmr_size, &store);
+    assert_eq!(mmr.get_root().unwrap(), root);
+    let proof = mmr.gen_proof(vec![positions[5]]).unwrap();
+    assert!(proof
+        .verify(root, vec![(positions[5], NumberHash::from(5))])
+        .unwrap());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_file_store_drop_uncommitted() {
+    let dir = temp_dir("uncommitted");
+    let store = NumberHashStore::open(&dir).unwrap();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    mmr.push(NumberHash::from(0)).unwrap();
+    mmr.commit().expect("commit changes");
+
+    // elems are readable before the store is committed
+    let mut store_ref = &store;
+    store_ref
+        .append(1, vec![NumberHash::from(1), NumberHash::from(2)])
+        .unwrap();
+    assert_eq!(store.mmr_size(), 3);
+    assert_eq!((&store).get_elem(1).unwrap(), Some(NumberHash::from(1)));
+
+    // crash before store commit
+    drop(store);
+    let store = NumberHashStore::open(&dir).unwrap();
+    assert_eq!(store.mmr_size(), 1);
+    assert_eq!((&store).get_elem(1).unwrap(), None);
+    assert_eq!(
+        fs::metadata(dir.join("mmr.data")).unwrap().len(),
+        32,
+        "uncommitted elems are dropped"
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_file_store_append_only() {
+    let dir = temp_dir("append-only");
+    let store = NumberHashStore::open(&dir).unwrap();
+    let mut store_ref = &store;
+    store_ref.append(0, vec![NumberHash::from(0)]).unwrap();
+    assert_eq!(
+        store_ref.append(3, vec![NumberHash::from(1)]),
+        Err(Error::StoreError("file store is append-only".into()))
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_file_store_rewind() {
+    let dir = temp_dir("rewind");
+    let store = NumberHashStore::open(&dir).unwrap();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    (0u32..11).for_each(|i| {
+        mmr.push(NumberHash::from(i)).unwrap();
+    });
+    mmr.commit().expect("commit changes");
+
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(store.mmr_size(), &store);
+    mmr.rewind(7).unwrap();
+    mmr.commit().expect("commit changes");
+    drop(store);
+    let store = NumberHashStore::open(&dir).unwrap();
+    assert_eq!(store.mmr_size(), 7);
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(7, &store);
+    (4u32..11).for_each(|i| {
+        mmr.push(NumberHash::from(i)).unwrap();
+    });
+    let mem_store = MemStore::default();
+    let mut mem_mmr = MMR::<_, MergeNumberHash, _>::new(0, &mem_store);
+    (0u32..11).for_each(|i| {
+        mem_mmr.push(NumberHash::from(i)).unwrap();
+    });
+    assert_eq!(mmr.get_root(), mem_mmr.get_root());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+fn mem_mmr_root(elems: impl Iterator<Item = u32>) -> NumberHash {
+    let mem_store = MemStore::default();
+    let mut mem_mmr = MMR::<_, MergeNumberHash, _>::new(0, &mem_store);
+    for i in elems {
+        mem_mmr.push(NumberHash::from(i)).unwrap();
+    }
+    mem_mmr.get_root().unwrap()
+}
+
+// truncate the store to `mmr_size` and append the following elems of a MMR of `leaves`,
+// without committing the store
+fn rewrite_uncommitted(store: &NumberHashStore, mmr_size: u64, leaves: impl Iterator<Item = u32>) {
+    let mem_store = MemStore::default();
+    let mut mem_mmr = MMR::<_, MergeNumberHash, _>::new(0, &mem_store);
+    for i in leaves {
+        mem_mmr.push(NumberHash::from(i)).unwrap();
+    }
+    let new_size = mem_mmr.mmr_size();
+    mem_mmr.commit().expect("commit changes");
+    let elems = (mmr_size..new_size)
+        .map(|pos| (&mem_store).get_elem(pos).unwrap().unwrap())
+        .collect();
+    let mut store_ref = store;
+    store_ref.truncate(mmr_size).unwrap();
+    store_ref.append(mmr_size, elems).unwrap();
+}
+
+#[test]
+fn test_file_store_truncate_uncommitted() {
+    let dir = temp_dir("truncate-uncommitted");
+    let store = NumberHashStore::open(&dir).unwrap();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    (0u32..11).for_each(|i| {
+        mmr.push(NumberHash::from(i)).unwrap();
+    });
+    let mmr_size = mmr.mmr_size();
+    mmr.commit().expect("commit changes");
+
+    // rewind and push other elems, which are readable before the store is committed
+    let new_root = mem_mmr_root((0u32..4).chain(100..107));
+    rewrite_uncommitted(&store, 7, (0u32..4).chain(100..107));
+    let mmr = MMR::<_, MergeNumberHash, _>::new(mmr_size, &store);
+    assert_eq!(mmr.get_root().unwrap(), new_root);
+
+    // crash before store commit, the committed elems are intact
+    drop(store);
+    let store = NumberHashStore::open(&dir).unwrap();
+    assert_eq!(store.mmr_size(), mmr_size);
+    let mmr = MMR::<_, MergeNumberHash, _>::new(mmr_size, &store);
+    assert_eq!(mmr.get_root().unwrap(), mem_mmr_root(0u32..11));
+
+    // commit this time
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(mmr_size, &store);
+    mmr.rewind(7).unwrap();
+    (100u32..107).for_each(|i| {
+        mmr.push(NumberHash::from(i)).unwrap();
+    });
+    mmr.commit().expect("commit changes");
+    assert!(!dir.join("mmr.journal").exists());
+    drop(store);
+    let store = NumberHashStore::open(&dir).unwrap();
+    assert_eq!(store.mmr_size(), mmr_size);
+    let mmr = MMR::<_, MergeNumberHash, _>::new(mmr_size, &store);
+    assert_eq!(mmr.get_root().unwrap(), new_root);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_file_store_failed_commit() {
+    let dir = temp_dir("failed-commit");
+    let store = NumberHashStore::open(&dir).unwrap();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    (0u32..11).for_each(|i| {
+        mmr.push(NumberHash::from(i)).unwrap();
+    });
+    let mmr_size = mmr.mmr_size();
+    mmr.commit().expect("commit changes");
+
+    // the journal can't be written while the directory is moved away
+    let new_root = mem_mmr_root((0u32..4).chain(100..107));
+    rewrite_uncommitted(&store, 7, (0u32..4).chain(100..107));
+    let moved_dir = dir.with_extension("moved");
+    let _ = fs::remove_dir_all(&moved_dir);
+    fs::rename(&dir, &moved_dir).unwrap();
+    assert!(store.commit().is_err());
+    fs::rename(&moved_dir, &dir).unwrap();
+
+    // pending elems are kept and committed by a retry
+    assert_eq!(store.mmr_size(), mmr_size);
+    let mmr = MMR::<_, MergeNumberHash, _>::new(mmr_size, &store);
+    assert_eq!(mmr.get_root().unwrap(), new_root);
+    store.commit().expect("commit store");
+    drop(store);
+    let store = NumberHashStore::open(&dir).unwrap();
+    assert_eq!(store.mmr_size(), mmr_size);
+    let mmr = MMR::<_, MergeNumberHash, _>::new(mmr_size, &store);
+    assert_eq!(mmr.get_root().unwrap(), new_root);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_file_store_replay_journal() {
+    let dir = temp_dir("journal");
+    let store = NumberHashStore::open(&dir).unwrap();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    (0u32..11).for_each(|i| {
+        mmr.push(NumberHash::from(i)).unwrap();
+    });
+    mmr.commit().expect("commit changes");
+    drop(store);
+
+    // crash after the journal of a rewind to 4 leaves and a new leaf is written
+    let mut journal = 7u64.to_le_bytes().to_vec();
+    journal.extend_from_slice(&NumberHash::from(100).0);
+    fs::write(dir.join("mmr.journal"), journal).unwrap();
+    let store = NumberHashStore::open(&dir).unwrap();
+    assert!(!dir.join("mmr.journal").exists());
+    assert_eq!(store.mmr_size(), 8);
+    assert_eq!((&store).get_elem(7).unwrap(), Some(NumberHash::from(100)));
+    let mmr = MMR::<_, MergeNumberHash, _>::new(8, &store);
+    assert_eq!(
+        mmr.get_root().unwrap(),
+        mem_mmr_root((0u32..4).chain(100..101))
+    );
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -1,13 +1,118 @@
 use super::{MergeNumberHash, NumberHash};
 use crate::{
     helper::{get_peaks, pos_height_in_tree},
-    leaf_index_to_mmr_size, leaf_index_to_pos,
+    leaf_count_to_mmr_size, leaf_index_to_mmr_size, leaf_index_to_pos, mmr_size_to_leaf_count,
+    pos_to_leaf_index,
     util::MemStore,
-    MMR,
+    Error, MAX_LEAF_COUNT, MMR,
 };
 use lazy_static::lazy_static;
 use proptest::prelude::*;
 
+/// The loop based implementations, kept to check the bit tricks
+mod reference {
+    fn log2(mut n: u64) -> u64 {
+        let mut k = 0;
+        while n > 1 {
+            k += 1;
+            n >>= 1;
+        }
+        k
+    }
+
+    fn get_peak_pos_by_height(height: u32) -> u64 {
+        (1 << (height + 1)) - 2
+    }
+
+    pub fn leaf_index_to_pos(index: u64) -> u64 {
+        if index == 0 {
+            return 0;
+        }
+        let mut leaves = index + 1;
+        let mut tree_node_count = 0;
+        let mut height = 0u32;
+        while leaves > 1 {
+            height = log2(leaves) as u32;
+            let peak_leaves = 1 << height;
+            let sub_tree_node_count = get_peak_pos_by_height(height) + 1;
+            tree_node_count += sub_tree_node_count;
+            leaves -= peak_leaves;
+        }
+        if leaves == 1 {
+            tree_node_count
+        } else {
+            let pos = tree_node_count - 1;
+            pos - u64::from(height)
+        }
+    }
+
+    pub fn leaf_index_to_mmr_size(index: u64) -> u64 {
+        let mut pos = leaf_index_to_pos(index);
+        while pos_height_in_tree(pos + 1) > pos_height_in_tree(pos) {
+            pos += 1
+        }
+        pos + 1
+    }
+
+    pub fn pos_height_in_tree(mut pos: u64) -> u32 {
+        pos += 1;
+        fn all_ones(num: u64) -> bool {
+            num != 0 && num.count_zeros() == num.leading_zeros()
+        }
+        fn jump_left(pos: u64) -> u64 {
+            let bit_length = 64 - pos.leading_zeros();
+            let most_significant_bits = 1 << (bit_length - 1);
+            pos - (most_significant_bits - 1)
+        }
+
+        while !all_ones(pos) {
+            pos = jump_left(pos)
+        }
+
+        64 - pos.leading_zeros() - 1
+    }
+
+    pub fn get_peaks(mmr_size: u64) -> Vec<u64> {
+        let mut pos_s = Vec::new();
+        let (mut height, mut pos) = left_peak_height_pos(mmr_size);
+        pos_s.push(pos);
+        while height > 0 {
+            let peak = match get_right_peak(height, pos, mmr_size) {
+                Some(peak) => peak,
+                None => break,
+            };
+            height = peak.0;
+            pos = peak.1;
+            pos_s.push(pos);
+        }
+        pos_s
+    }
+
+    fn get_right_peak(mut height: u32, mut pos: u64, mmr_size: u64) -> Option<(u32, u64)> {
+        pos += (2 << height) - 1;
+        while pos > mmr_size - 1 {
+            if height == 0 {
+                return None;
+            }
+            pos -= 2 << (height - 1);
+            height -= 1;
+        }
+        Some((height, pos))
+    }
+
+    fn left_peak_height_pos(mmr_size: u64) -> (u32, u64) {
+        let mut height = 1;
+        let mut prev_pos = 0;
+        let mut pos = get_peak_pos_by_height(height);
+        while pos < mmr_size {
+            height += 1;
+            prev_pos = pos;
+            pos = get_peak_pos_by_height(height);
+        }
+        (height - 1, prev_pos)
+    }
+}
+
 lazy_static! {
     /// Positions of 0..100_000 elem
     static ref INDEX_TO_POS: Vec<u64> = {
@@ -53,6 +158,10 @@ fn test_pos_height_in_tree() {
     assert_eq!(pos_height_in_tree(4), 0);
     assert_eq!(pos_height_in_tree(6), 2);
     assert_eq!(pos_height_in_tree(7), 0);
+    // the root and the last leaf of the largest MMR
+    assert_eq!(pos_height_in_tree(u64::MAX - 1), 63);
+    assert_eq!(pos_height_in_tree(u64::MAX - 2), 62);
+    assert_eq!(pos_height_in_tree(leaf_index_to_pos(MAX_LEAF_COUNT - 1)), 0);
 }
 
 #[test]
@@ -68,6 +177,49 @@ fn test_get_peaks() {
     assert_eq!(get_peaks(19), vec![14, 17, 18]);
 }
 
+#[test]
+fn test_pos_to_leaf_index() {
+    assert_eq!(pos_to_leaf_index(0), Ok(0));
+    assert_eq!(pos_to_leaf_index(1), Ok(1));
+    assert_eq!(pos_to_leaf_index(2), Err(Error::InvalidLeafPosition));
+    assert_eq!(pos_to_leaf_index(3), Ok(2));
+    assert_eq!(pos_to_leaf_index(6), Err(Error::InvalidLeafPosition));
+    assert_eq!(pos_to_leaf_index(7), Ok(4));
+}
+
+#[test]
+fn test_mmr_size_to_leaf_count() {
+    assert_eq!(mmr_size_to_leaf_count(0), Ok(0));
+    assert_eq!(mmr_size_to_leaf_count(1), Ok(1));
+    assert_eq!(mmr_size_to_leaf_count(2), Err(Error::InvalidMMRSize));
+    assert_eq!(mmr_size_to_leaf_count(3), Ok(2));
+    assert_eq!(mmr_size_to_leaf_count(4), Ok(3));
+    assert_eq!(mmr_size_to_leaf_count(5), Err(Error::InvalidMMRSize));
+    assert_eq!(mmr_size_to_leaf_count(19), Ok(11));
+    assert_eq!(mmr_size_to_leaf_count(u64::MAX), Ok(1 << 63));
+}
+
+#[test]
+fn test_leaf_count_to_mmr_size() {
+    assert_eq!(leaf_count_to_mmr_size(0), 0);
+    assert_eq!(leaf_count_to_mmr_size(1), 1);
+    assert_eq!(leaf_count_to_mmr_size(2), 3);
+    assert_eq!(leaf_count_to_mmr_size(3), 4);
+    assert_eq!(leaf_count_to_mmr_size(11), 19);
+    // the largest MMR
+    assert_eq!(leaf_count_to_mmr_size(MAX_LEAF_COUNT), u64::MAX);
+    assert_eq!(leaf_count_to_mmr_size(MAX_LEAF_COUNT - 1), u64::MAX - 64);
+    assert_eq!(leaf_index_to_mmr_size(MAX_LEAF_COUNT - 1), u64::MAX);
+    assert_eq!(leaf_index_to_pos(MAX_LEAF_COUNT - 1), u64::MAX - 64);
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "out of MMR size")]
+fn test_leaf_count_to_mmr_size_overflow() {
+    leaf_count_to_mmr_size(MAX_LEAF_COUNT + 1);
+}
+
 proptest! {
     #[test]
     fn test_leaf_index_to_pos_randomly(index in 0..INDEX_TO_POS.len()) {
@@ -79,4 +231,41 @@ proptest! {
     fn test_leaf_index_to_mmr_size_randomly(index in 0..INDEX_TO_MMR_SIZE.len()) {
         assert_eq!(leaf_index_to_mmr_size(index as u64), INDEX_TO_MMR_SIZE[index]);
     }
+
+    #[test]
+    fn test_pos_to_leaf_index_randomly(index in 0..INDEX_TO_POS.len()) {
+        assert_eq!(pos_to_leaf_index(INDEX_TO_POS[index]), Ok(index as u64));
+    }
+
+    #[test]
+    fn test_leaf_count_to_mmr_size_randomly(index in 0..INDEX_TO_MMR_SIZE.len()) {
+        let leaf_count = index as u64 + 1;
+        assert_eq!(leaf_count_to_mmr_size(leaf_count), INDEX_TO_MMR_SIZE[index]);
+        assert_eq!(mmr_size_to_leaf_count(INDEX_TO_MMR_SIZE[index]), Ok(leaf_count));
+    }
+
+    #[test]
+    fn test_leaf_index_to_pos_bit_tricks(index in 0u64..(1 << 40)) {
+        assert_eq!(leaf_index_to_pos(index), reference::leaf_index_to_pos(index));
+    }
+
+    #[test]
+    fn test_leaf_index_to_mmr_size_bit_tricks(index in 0u64..(1 << 40)) {
+        assert_eq!(leaf_index_to_mmr_size(index), reference::leaf_index_to_mmr_size(index));
+    }
+
+    #[test]
+    fn test_pos_height_in_tree_bit_tricks(pos in 0u64..(1 << 50)) {
+        assert_eq!(pos_height_in_tree(pos), reference::pos_height_in_tree(pos));
+    }
+
+    #[test]
+    fn test_get_peaks_bit_tricks(mmr_size in 0u64..(1 << 50)) {
+        assert_eq!(get_peaks(mmr_size), reference::get_peaks(mmr_size));
+    }
+
+    #[test]
+    fn test_small_get_peaks_bit_tricks(mmr_size in 0u64..10_000) {
+        assert_eq!(get_peaks(mmr_size), reference::get_peaks(mmr_size));
+    }
 }
@@ -0,0 +1,188 @@
+use super::{MergeNumberHash, NumberHash, NumberHashCodec};
+use crate::{
+    util::{MemKVStore, MemStore},
+    KVMMRStore, KVStore, MMRStore, Result, MMR,
+};
+use core::cell::Cell;
+
+type NumberHashKVStore<'a> = KVMMRStore<NumberHash, NumberHashCodec, &'a MemKVStore>;
+
+fn mem_mmr_root(count: u32) -> NumberHash {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    (0u32..count).for_each(|i| {
+        mmr.push(NumberHash::from(i)).unwrap();
+    });
+    mmr.get_root().unwrap()
+}
+
+#[test]
+fn test_kv_store() {
+    let kv = MemKVStore::default();
+    let mut mmr =
+        MMR::<_, MergeNumberHash, _>::new(0, NumberHashKVStore::new(&kv, b"mmr".to_vec()));
+    let positions: Vec<u64> = (0u32..11)
+        .map(|i| mmr.push(NumberHash::from(i)).unwrap())
+        .collect();
+    let mmr_size = mmr.mmr_size();
+    mmr.commit().expect("commit changes");
+
+    let store = NumberHashKVStore::new(&kv, b"mmr".to_vec());
+    assert_eq!(store.mmr_size(), Ok(mmr_size));
+    // elems and the size
+    assert_eq!(kv.len() as u64, mmr_size + 1);
+    let mut key = vec![0, 0, 0, 3];
+    key.extend_from_slice(b"mmr");
+    key.push(0);
+    key.extend_from_slice(&positions[1].to_be_bytes());
+    assert_eq!(
+        (&kv).get(&key).unwrap(),
+        Some(NumberHash::from(1).0.to_vec())
+    );
+
+    let mmr = MMR::<_, MergeNumberHash, _>::new(mmr_size, store);
+    let root = mmr.get_root().unwrap();
+    assert_eq!(root, mem_mmr_root(11));
+    let proof = mmr.gen_proof(vec![positions[5]]).unwrap();
+    assert!(proof
+        .verify(root, vec![(positions[5], NumberHash::from(5))])
+        .unwrap());
+}
+
+#[test]
+fn test_kv_store_prefix() {
+    let kv = MemKVStore::default();
+    for (prefix, count) in &[(b"a".to_vec(), 11u32), (Vec::new(), 5), (b"b".to_vec(), 7)] {
+        let mut mmr =
+            MMR::<_, MergeNumberHash, _>::new(0, NumberHashKVStore::new(&kv, prefix.clone()));
+        (0u32..*count).for_each(|i| {
+            mmr.push(NumberHash::from(i + 100)).unwrap();
+        });
+        mmr.commit().expect("commit changes");
+    }
+    for (prefix, count) in &[(b"a".to_vec(), 11u32), (Vec::new(), 5), (b"b".to_vec(), 7)] {
+        let store = NumberHashKVStore::new(&kv, prefix.clone());
+        let mmr_size = store.mmr_size().unwrap();
+        assert_eq!(mmr_size, crate::leaf_count_to_mmr_size(u64::from(*count)));
+        assert_eq!(
+            store.get_elem(0).unwrap(),
+            Some(NumberHash::from(100)),
+            "prefix {:?}",
+            prefix
+        );
+        assert_eq!(store.get_elem(mmr_size).unwrap(), None);
+    }
+}
+
+#[test]
+fn test_kv_store_prefix_collision() {
+    let kv = MemKVStore::default();
+    // without the length of prefix, the size key of the second prefix
+    // would be the key of elem at pos 1 of the first one
+    let mut long_prefix = b"a".to_vec();
+    long_prefix.extend_from_slice(&[0u8; 8]);
+    for (prefix, count) in &[(b"a".to_vec(), 11u32), (long_prefix.clone(), 3)] {
+        let mut mmr =
+            MMR::<_, MergeNumberHash, _>::new(0, NumberHashKVStore::new(&kv, prefix.clone()));
+        (0u32..*count).for_each(|i| {
+            mmr.push(NumberHash::from(i)).unwrap();
+        });
+        mmr.commit().expect("commit changes");
+    }
+    let store = NumberHashKVStore::new(&kv, b"a".to_vec());
+    assert_eq!(store.get_elem(1).unwrap(), Some(NumberHash::from(1)));
+    let mmr = MMR::<_, MergeNumberHash, _>::new(store.mmr_size().unwrap(), store);
+    assert_eq!(mmr.get_root().unwrap(), mem_mmr_root(11));
+    let store = NumberHashKVStore::new(&kv, long_prefix);
+    assert_eq!(store.mmr_size(), Ok(crate::leaf_count_to_mmr_size(3)));
+}
+
+/// Count batches written to the inner store
+#[derive(Default)]
+struct CountingKVStore {
+    kv: MemKVStore,
+    batches: Cell<usize>,
+}
+
+impl KVStore for &CountingKVStore {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        (&self.kv).get(key)
+    }
+
+    fn write_batch(&mut self, batch: Vec<(Vec<u8>, Option<Vec<u8>>)>) -> Result<()> {
+        self.batches.set(self.batches.get() + 1);
+        (&self.kv).write_batch(batch)
+    }
+}
+
+#[test]
+fn test_kv_store_commit_in_one_batch() {
+    let kv = CountingKVStore::default();
+    let new_store = || KVMMRStore::<NumberHash, NumberHashCodec, _>::new(&kv, Vec::new());
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, new_store());
+    (0u32..11).for_each(|i| {
+        mmr.push(NumberHash::from(i)).unwrap();
+    });
+    let mmr_size = mmr.mmr_size();
+    mmr.commit().expect("commit changes");
+    assert_eq!(kv.batches.get(), 1);
+
+    // rewind and push again, the truncate and every append are in the same batch
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(mmr_size, new_store());
+    mmr.rewind(7).unwrap();
+    mmr.commit().expect("commit changes");
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(7, new_store());
+    mmr.rewind(4).unwrap();
+    (3u32..11).for_each(|i| {
+        mmr.push(NumberHash::from(i)).unwrap();
+    });
+    mmr.commit().expect("commit changes");
+    assert_eq!(kv.batches.get(), 3);
+    let store = new_store();
+    assert_eq!(store.mmr_size(), Ok(mmr_size));
+    let mmr = MMR::<_, MergeNumberHash, _>::new(mmr_size, store);
+    assert_eq!(mmr.get_root().unwrap(), mem_mmr_root(11));
+
+    // writes are pending until commit
+    let mut store = new_store();
+    store.append(mmr_size, vec![NumberHash::from(11)]).unwrap();
+    assert_eq!(store.mmr_size(), Ok(mmr_size + 1));
+    assert_eq!(
+        store.get_elem(mmr_size).unwrap(),
+        Some(NumberHash::from(11))
+    );
+    assert_eq!(kv.kv.len() as u64, mmr_size + 1);
+    store.commit().unwrap();
+    assert_eq!(kv.kv.len() as u64, mmr_size + 2);
+}
+
+#[test]
+fn test_kv_store_rewind_and_prune() {
+    let kv = MemKVStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, NumberHashKVStore::new(&kv, Vec::new()));
+    (0u32..11).for_each(|i| {
+        mmr.push(NumberHash::from(i)).unwrap();
+    });
+    let mmr_size = mmr.mmr_size();
+    mmr.commit().expect("commit changes");
+
+    let mut mmr =
+        MMR::<_, MergeNumberHash, _>::new(mmr_size, NumberHashKVStore::new(&kv, Vec::new()));
+    mmr.rewind(7).unwrap();
+    mmr.commit().expect("commit changes");
+    let store = NumberHashKVStore::new(&kv, Vec::new());
+    assert_eq!(store.mmr_size(), Ok(7));
+    assert_eq!(kv.len(), 7 + 1);
+
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(7, store);
+    (4u32..11).for_each(|i| {
+        mmr.push(NumberHash::from(i)).unwrap();
+    });
+    assert_eq!(mmr.get_root().unwrap(), mem_mmr_root(11));
+    // prune the subtree of the first peak
+    mmr.prune(vec![6]).unwrap();
+    mmr.commit().expect("commit changes");
+    let store = NumberHashKVStore::new(&kv, Vec::new());
+    assert_eq!(store.get_elem(0).unwrap(), None);
+    assert!(store.get_elem(6).unwrap().is_some());
+}
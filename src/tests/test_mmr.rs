@@ -1,5 +1,6 @@
 use super::{MergeNumberHash, NumberHash};
-use crate::{leaf_index_to_mmr_size, util::MemStore, Error, MMR};
+use crate::{leaf_index_to_mmr_size, util::MemStore, Error, MMRStore, Result, MMR};
+use core::cell::Cell;
 use faster_hex::hex_string;
 use proptest::prelude::*;
 use rand::{seq::SliceRandom, thread_rng};
@@ -32,6 +33,28 @@ fn test_mmr(count: u32, proof_elem: Vec<u32>) {
     assert!(result);
 }
 
+fn test_mmr_by_leaf_indices(count: u32, proof_elem: Vec<u32>) {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    (0u32..count).for_each(|i| {
+        mmr.push(NumberHash::from(i)).unwrap();
+    });
+    let root = mmr.get_root().expect("get root");
+    let proof = mmr
+        .gen_proof_by_leaf_indices(proof_elem.iter().map(|&elem| u64::from(elem)).collect())
+        .expect("gen proof");
+    let result = proof
+        .verify_by_leaf_indices(
+            root,
+            proof_elem
+                .iter()
+                .map(|&elem| (u64::from(elem), NumberHash::from(elem)))
+                .collect(),
+        )
+        .unwrap();
+    assert!(result);
+}
+
 fn test_gen_new_root_from_proof(count: u32) {
     let store = MemStore::default();
     let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
@@ -135,11 +158,232 @@ fn test_mmr_3_leaves_merkle_proof() {
     test_mmr(100, vec![3, 5, 13]);
 }
 
+#[test]
+fn test_mmr_proof_by_leaf_indices() {
+    test_mmr_by_leaf_indices(11, vec![5]);
+    test_mmr_by_leaf_indices(11, vec![0, 10]);
+    test_mmr_by_leaf_indices(100, vec![3, 5, 13]);
+    test_mmr_by_leaf_indices(1, vec![0]);
+}
+
+#[test]
+fn test_mmr_proof_by_invalid_leaf_indices() {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    (0u32..11).for_each(|i| {
+        mmr.push(NumberHash::from(i)).unwrap();
+    });
+    assert_eq!(
+        mmr.gen_proof_by_leaf_indices(vec![11]).map(|_| ()),
+        Err(Error::GenProofForInvalidLeaves)
+    );
+    assert_eq!(
+        mmr.gen_proof_by_leaf_indices(vec![5, u64::MAX]).map(|_| ()),
+        Err(Error::GenProofForInvalidLeaves)
+    );
+
+    // leaf indices out of the MMR of proof
+    let root = mmr.get_root().expect("get root");
+    let proof = mmr.gen_proof_by_leaf_indices(vec![5]).expect("gen proof");
+    for leaf_index in [11, u64::MAX] {
+        assert_eq!(
+            proof.verify_by_leaf_indices(root.clone(), vec![(leaf_index, NumberHash::from(5))]),
+            Err(Error::CorruptedProof)
+        );
+    }
+}
+
 #[test]
 fn test_gen_root_from_proof() {
     test_gen_new_root_from_proof(11);
 }
 
+fn test_rewind(count: u32, rewind_count: u32) {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    (0u32..count).for_each(|i| {
+        mmr.push(NumberHash::from(i)).unwrap();
+    });
+    let mmr_size = mmr.mmr_size();
+    mmr.commit().expect("commit changes");
+
+    let rewind_size = leaf_index_to_mmr_size(u64::from(rewind_count) - 1);
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(mmr_size, &store);
+    mmr.rewind(rewind_size).expect("rewind");
+    assert_eq!(mmr.mmr_size(), rewind_size);
+    // push again after rewind, the rewound nodes must not be read back
+    let pos = mmr.push(NumberHash::from(count)).unwrap();
+    assert_eq!(pos, rewind_size);
+    let root = mmr.get_root().expect("get root");
+    let new_mmr_size = mmr.mmr_size();
+    mmr.commit().expect("commit changes");
+
+    let expected_store = MemStore::default();
+    let mut expected_mmr = MMR::<_, MergeNumberHash, _>::new(0, &expected_store);
+    (0u32..rewind_count).for_each(|i| {
+        expected_mmr.push(NumberHash::from(i)).unwrap();
+    });
+    expected_mmr.push(NumberHash::from(count)).unwrap();
+    assert_eq!(expected_mmr.mmr_size(), new_mmr_size);
+    assert_eq!(expected_mmr.get_root().expect("get root"), root);
+    for pos in 0..mmr_size {
+        assert_eq!(
+            (&store).get_elem(pos).unwrap().is_some(),
+            pos < new_mmr_size
+        );
+    }
+}
+
+#[test]
+fn test_rewind_mmr() {
+    test_rewind(11, 7);
+    test_rewind(11, 8);
+    test_rewind(11, 11);
+    test_rewind(2, 1);
+}
+
+#[test]
+fn test_rewind_uncommitted() {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    (0u32..4).for_each(|i| {
+        mmr.push(NumberHash::from(i)).unwrap();
+    });
+    let root = mmr.get_root().expect("get root");
+    let mmr_size = mmr.mmr_size();
+    (4u32..9).for_each(|i| {
+        mmr.push(NumberHash::from(i)).unwrap();
+    });
+    mmr.rewind(mmr_size).expect("rewind");
+    assert_eq!(mmr.get_root().expect("get root"), root);
+    mmr.commit().expect("commit changes");
+    assert!((&store).get_elem(mmr_size - 1).unwrap().is_some());
+    assert!((&store).get_elem(mmr_size).unwrap().is_none());
+}
+
+#[test]
+fn test_rewind_invalid_size() {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    (0u32..11).for_each(|i| {
+        mmr.push(NumberHash::from(i)).unwrap();
+    });
+    // 2 is not a valid mmr size
+    assert_eq!(mmr.rewind(2), Err(Error::InvalidMMRSize));
+    // can't rewind forward
+    let mmr_size = mmr.mmr_size();
+    assert_eq!(mmr.rewind(mmr_size + 1), Err(Error::InvalidMMRSize));
+    assert_eq!(mmr.mmr_size(), mmr_size);
+}
+
+fn test_push_batch(count: u32, batch_count: u32) {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    let positions: Vec<u64> = (0u32..count + batch_count)
+        .map(|i| mmr.push(NumberHash::from(i)).unwrap())
+        .collect();
+    let root = mmr.get_root().expect("get root");
+    let mmr_size = mmr.mmr_size();
+    mmr.commit().expect("commit changes");
+
+    let batch_store = MemStore::default();
+    let mut batch_mmr = MMR::<_, MergeNumberHash, _>::new(0, &batch_store);
+    let mut batch_positions = batch_mmr
+        .push_batch((0u32..count).map(NumberHash::from))
+        .unwrap();
+    batch_positions.extend(
+        batch_mmr
+            .push_batch((count..count + batch_count).map(NumberHash::from))
+            .unwrap(),
+    );
+    assert_eq!(batch_positions, positions);
+    assert_eq!(batch_mmr.mmr_size(), mmr_size);
+    assert_eq!(batch_mmr.get_root().expect("get root"), root);
+    batch_mmr.commit().expect("commit changes");
+    for pos in 0..mmr_size {
+        assert_eq!(
+            (&batch_store).get_elem(pos).unwrap(),
+            (&store).get_elem(pos).unwrap()
+        );
+    }
+}
+
+#[test]
+fn test_mmr_push_batch() {
+    test_push_batch(0, 11);
+    test_push_batch(7, 1);
+    test_push_batch(11, 100);
+}
+
+#[test]
+fn test_mmr_push_empty_batch() {
+    let store = MemStore::<NumberHash>::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    assert!(mmr.push_batch(Vec::new()).unwrap().is_empty());
+    assert!(mmr.is_empty());
+}
+
+// count round trips to the store
+struct CountingStore<'a> {
+    store: &'a MemStore<NumberHash>,
+    get_elem_calls: Cell<usize>,
+    get_elems_calls: Cell<usize>,
+}
+
+impl MMRStore<NumberHash> for &CountingStore<'_> {
+    fn get_elem(&self, pos: u64) -> Result<Option<NumberHash>> {
+        self.get_elem_calls.set(self.get_elem_calls.get() + 1);
+        self.store.get_elem(pos)
+    }
+
+    fn get_elems(&self, pos_list: &[u64]) -> Result<Vec<Option<NumberHash>>> {
+        self.get_elems_calls.set(self.get_elems_calls.get() + 1);
+        pos_list
+            .iter()
+            .map(|&pos| self.store.get_elem(pos))
+            .collect()
+    }
+
+    fn append(&mut self, pos: u64, elems: Vec<NumberHash>) -> Result<()> {
+        let mut store = self.store;
+        store.append(pos, elems)
+    }
+}
+
+#[test]
+fn test_gen_proof_batch_read() {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    let positions: Vec<u64> = (0u32..100)
+        .map(|i| mmr.push(NumberHash::from(i)).unwrap())
+        .collect();
+    let mmr_size = mmr.mmr_size();
+    let root = mmr.get_root().unwrap();
+    mmr.commit().expect("commit changes");
+
+    let counting_store = CountingStore {
+        store: &store,
+        get_elem_calls: Cell::new(0),
+        get_elems_calls: Cell::new(0),
+    };
+    let mmr = MMR::<_, MergeNumberHash, _>::new(mmr_size, &counting_store);
+    let leaves = [3usize, 17, 42, 99];
+    let proof = mmr
+        .gen_proof(leaves.iter().map(|&i| positions[i]).collect())
+        .unwrap();
+    assert_eq!(counting_store.get_elems_calls.get(), 1);
+    assert_eq!(counting_store.get_elem_calls.get(), 0);
+    assert!(proof
+        .verify(
+            root,
+            leaves
+                .iter()
+                .map(|&i| (positions[i], NumberHash::from(i as u32)))
+                .collect()
+        )
+        .unwrap());
+}
+
 prop_compose! {
     fn count_elem(count: u32)
                 (elem in 0..count)
@@ -159,6 +403,16 @@ proptest! {
         test_mmr(count, leaves);
     }
 
+    #[test]
+    fn test_random_rewind((count, rewind_count) in (2u32..500u32).prop_flat_map(|count| (Just(count), 1..count))) {
+        test_rewind(count, rewind_count);
+    }
+
+    #[test]
+    fn test_random_push_batch(count in 0u32..500u32, batch_count in 0u32..500u32) {
+        test_push_batch(count, batch_count);
+    }
+
     #[test]
     fn test_random_gen_root_with_new_leaf(count in 1u32..500u32) {
         test_gen_new_root_from_proof(count);
@@ -0,0 +1,205 @@
+use super::{new_blake2b, MergeNumberHash, NumberHash};
+use crate::{
+    leaf_index_to_mmr_size, leaf_index_to_pos, util::MemStore, verify_without_alloc, MMRStore,
+    NodeMerge, PeaksAccumulator, Result, MMR,
+};
+use proptest::prelude::*;
+use rand::{thread_rng, Rng};
+
+/// Commit position and height of nodes in the style of RFC 6962 domain separation
+struct MergeNumberHashWithPos;
+
+impl MergeNumberHashWithPos {
+    fn hash(parts: &[&[u8]]) -> NumberHash {
+        let mut hasher = new_blake2b();
+        let mut hash = [0u8; 32];
+        for part in parts {
+            hasher.update(part);
+        }
+        hasher.finalize(&mut hash);
+        NumberHash(hash.to_vec().into())
+    }
+}
+
+impl NodeMerge for MergeNumberHashWithPos {
+    type Item = NumberHash;
+    fn hash_leaf(leaf: Self::Item) -> Result<Self::Item> {
+        Ok(Self::hash(&[&[0u8], &leaf.0]))
+    }
+    fn merge_node(
+        parent_pos: u64,
+        height: u32,
+        left: &Self::Item,
+        right: &Self::Item,
+    ) -> Result<Self::Item> {
+        Ok(Self::hash(&[
+            &[1u8],
+            &parent_pos.to_le_bytes(),
+            &height.to_le_bytes(),
+            &left.0,
+            &right.0,
+        ]))
+    }
+    fn merge_peaks(left: &Self::Item, right: &Self::Item) -> Result<Self::Item> {
+        Ok(Self::hash(&[&[2u8], &left.0, &right.0]))
+    }
+}
+
+fn leaf(i: u32) -> NumberHash {
+    MergeNumberHashWithPos::hash_leaf(NumberHash::from(i)).unwrap()
+}
+
+fn test_node_merge(count: u32, proof_elem: u32) {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHashWithPos, _>::new(0, &store);
+    let mut accumulator = PeaksAccumulator::<_, MergeNumberHashWithPos>::default();
+    let positions: Vec<u64> = (0u32..count)
+        .map(|i| {
+            accumulator.push(NumberHash::from(i)).unwrap();
+            mmr.push(NumberHash::from(i)).unwrap()
+        })
+        .collect();
+    let root = mmr.get_root().expect("get root");
+    assert_eq!(accumulator.get_root().expect("get root"), root);
+    let pos = positions[proof_elem as usize];
+    let proof = mmr.gen_proof(vec![pos]).expect("gen proof");
+    mmr.commit().expect("commit changes");
+    assert_eq!((&store).get_elem(pos).unwrap(), Some(leaf(proof_elem)));
+    // leaves are hashed when verified, as in push
+    assert!(proof
+        .verify(root.clone(), vec![(pos, NumberHash::from(proof_elem))])
+        .unwrap());
+    assert!(!proof
+        .verify(root.clone(), vec![(pos, leaf(proof_elem))])
+        .unwrap());
+    // stored nodes are verified as they are
+    assert!(proof
+        .verify_nodes(root.clone(), vec![(pos, leaf(proof_elem))])
+        .unwrap());
+    assert!(proof
+        .verify_by_leaf_indices(
+            root,
+            vec![(proof_elem as u64, NumberHash::from(proof_elem))]
+        )
+        .unwrap());
+}
+
+#[test]
+fn test_node_merge_proof() {
+    test_node_merge(11, 5);
+    test_node_merge(1, 0);
+    test_node_merge(8, 7);
+}
+
+#[test]
+fn test_node_merge_push_batch() {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHashWithPos, _>::new(0, &store);
+    (0u32..11).for_each(|i| {
+        mmr.push(NumberHash::from(i)).unwrap();
+    });
+    let batch_store = MemStore::default();
+    let mut batch_mmr = MMR::<_, MergeNumberHashWithPos, _>::new(0, &batch_store);
+    batch_mmr
+        .push_batch((0u32..11).map(NumberHash::from))
+        .unwrap();
+    assert_eq!(batch_mmr.get_root(), mmr.get_root());
+}
+
+#[test]
+fn test_node_merge_commits_position() {
+    // subtrees at pos 2 and pos 5 have the same leaves
+    let elems = [0u32, 1, 0, 1];
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    let pos_store = MemStore::default();
+    let mut pos_mmr = MMR::<_, MergeNumberHashWithPos, _>::new(0, &pos_store);
+    for &i in &elems {
+        mmr.push(NumberHash::from(i)).unwrap();
+        pos_mmr.push(NumberHash::from(i)).unwrap();
+    }
+    mmr.commit().expect("commit changes");
+    pos_mmr.commit().expect("commit changes");
+    assert_eq!((&store).get_elem(2).unwrap(), (&store).get_elem(5).unwrap());
+    assert_ne!(
+        (&pos_store).get_elem(2).unwrap(),
+        (&pos_store).get_elem(5).unwrap()
+    );
+}
+
+#[test]
+fn test_node_merge_ancestry_proof() {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHashWithPos, _>::new(0, &store);
+    let mut prev_root = None;
+    for i in 0u32..20 {
+        mmr.push(NumberHash::from(i)).unwrap();
+        if i == 6 {
+            prev_root = Some(mmr.get_root().expect("get root"));
+        }
+    }
+    let proof = mmr
+        .gen_ancestry_proof(leaf_index_to_mmr_size(6))
+        .expect("gen ancestry proof");
+    assert!(proof
+        .verify_ancestor(mmr.get_root().unwrap(), prev_root.unwrap())
+        .unwrap());
+}
+
+#[test]
+fn test_node_merge_verifiers() {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHashWithPos, _>::new(0, &store);
+    let mut accumulator = PeaksAccumulator::<_, MergeNumberHashWithPos>::default();
+    for i in 0u32..11 {
+        mmr.push(NumberHash::from(i)).unwrap();
+        accumulator.push(NumberHash::from(i)).unwrap();
+    }
+    let root = mmr.get_root().expect("get root");
+    let elems: Vec<NumberHash> = (0u32..11).map(NumberHash::from).collect();
+
+    // all verifiers take the elems pushed into MMR
+    let range_proof = mmr.gen_range_proof(3, 9).expect("gen range proof");
+    assert!(range_proof.verify(root.clone(), 3, &elems[3..9]).unwrap());
+    let leaf_pos = leaf_index_to_pos(10);
+    let proof = mmr.gen_proof(vec![leaf_pos]).expect("gen proof");
+    assert!(verify_without_alloc::<_, MergeNumberHashWithPos, 16>(
+        &root,
+        proof.mmr_size(),
+        proof.proof_items(),
+        &[(leaf_pos, elems[10].clone())],
+    )
+    .unwrap());
+
+    // proof of the last leaf is extended by a new leaf
+    let leaves = vec![(leaf_pos, elems[10].clone())];
+    let new_pos = mmr.push(NumberHash::from(11)).unwrap();
+    let new_root = mmr.get_root().expect("get root");
+    assert_eq!(
+        proof
+            .calculate_root_with_new_leaf(
+                leaves.clone(),
+                new_pos,
+                NumberHash::from(11),
+                mmr.mmr_size()
+            )
+            .unwrap(),
+        new_root
+    );
+    let new_proof = proof
+        .update_with_new_leaves(
+            leaves.clone(),
+            accumulator.peaks().to_vec(),
+            vec![NumberHash::from(11)],
+        )
+        .expect("update proof");
+    assert!(new_proof.verify(new_root, leaves).unwrap());
+}
+
+proptest! {
+    #[test]
+    fn test_random_node_merge(count in 1u32..500u32) {
+        let proof_elem = thread_rng().gen_range(0, count);
+        test_node_merge(count, proof_elem);
+    }
+}
@@ -0,0 +1,71 @@
+use super::{MergeNumberHash, NumberHash, NumberHashCodec};
+use crate::{
+    build_parallel,
+    util::{MemKVStore, MemStore},
+    KVMMRStore, MMRStore, MMR,
+};
+use proptest::prelude::*;
+
+fn test_build_parallel(count: u32) {
+    let leaves: Vec<NumberHash> = (0..count).map(NumberHash::from).collect();
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    for leaf in &leaves {
+        mmr.push(leaf.clone()).unwrap();
+    }
+    let mmr_size = mmr.mmr_size();
+    mmr.commit().expect("commit changes");
+
+    let parallel_store = MemStore::default();
+    assert_eq!(
+        build_parallel::<_, MergeNumberHash, _>(&leaves, &parallel_store),
+        Ok(mmr_size)
+    );
+    for pos in 0..mmr_size {
+        assert_eq!(
+            (&parallel_store).get_elem(pos).unwrap(),
+            (&store).get_elem(pos).unwrap()
+        );
+    }
+    assert_eq!((&parallel_store).get_elem(mmr_size).unwrap(), None);
+}
+
+#[test]
+fn test_build_parallel_small() {
+    test_build_parallel(0);
+    test_build_parallel(1);
+    test_build_parallel(11);
+}
+
+#[test]
+fn test_build_parallel_large() {
+    // larger than the sequential threshold
+    test_build_parallel(5000);
+}
+
+#[test]
+fn test_build_parallel_kv_store() {
+    // writes of the store are buffered until commit
+    let leaves: Vec<NumberHash> = (0u32..11).map(NumberHash::from).collect();
+    let kv = MemKVStore::default();
+    let store = KVMMRStore::<NumberHash, NumberHashCodec, _>::new(&kv, Vec::new());
+    let mmr_size = build_parallel::<_, MergeNumberHash, _>(&leaves, store).unwrap();
+    assert_eq!(kv.len() as u64, mmr_size + 1);
+
+    let store = KVMMRStore::<NumberHash, NumberHashCodec, _>::new(&kv, Vec::new());
+    assert_eq!(store.mmr_size(), Ok(mmr_size));
+    let mmr = MMR::<_, MergeNumberHash, _>::new(mmr_size, store);
+    let mem_store = MemStore::default();
+    let mut mem_mmr = MMR::<_, MergeNumberHash, _>::new(0, &mem_store);
+    for leaf in leaves {
+        mem_mmr.push(leaf).unwrap();
+    }
+    assert_eq!(mmr.get_root(), mem_mmr.get_root());
+}
+
+proptest! {
+    #[test]
+    fn test_random_build_parallel(count in 1u32..500u32) {
+        test_build_parallel(count);
+    }
+}
@@ -0,0 +1,76 @@
+use super::{MergeNumberHash, NumberHash};
+use crate::{helper::get_peaks, util::MemStore, Error, MMRStore, PeaksAccumulator, MMR};
+use proptest::prelude::*;
+
+#[test]
+fn test_accumulator_root() {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    let mut accumulator = PeaksAccumulator::<_, MergeNumberHash>::default();
+    assert_eq!(accumulator.get_root(), Err(Error::GetRootOnEmpty));
+    for i in 0u32..100 {
+        let pos = mmr.push(NumberHash::from(i)).unwrap();
+        assert_eq!(accumulator.push(NumberHash::from(i)).unwrap(), pos);
+        assert_eq!(accumulator.mmr_size(), mmr.mmr_size());
+        assert_eq!(accumulator.get_root(), mmr.get_root());
+    }
+}
+
+#[test]
+fn test_accumulator_invalid_peaks() {
+    assert_eq!(
+        PeaksAccumulator::<_, MergeNumberHash>::new(2, vec![NumberHash::from(0)]).map(|_| ()),
+        Err(Error::InvalidMMRSize)
+    );
+    // MMR of size 4 has 2 peaks
+    assert_eq!(
+        PeaksAccumulator::<_, MergeNumberHash>::new(4, vec![NumberHash::from(0)]).map(|_| ()),
+        Err(Error::InvalidMMRSize)
+    );
+    assert!(PeaksAccumulator::<NumberHash, MergeNumberHash>::new(0, Vec::new()).is_ok());
+}
+
+fn test_accumulator_from_peaks(count: u32, new_count: u32) {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    (0u32..count).for_each(|i| {
+        mmr.push(NumberHash::from(i)).unwrap();
+    });
+    let mmr_size = mmr.mmr_size();
+    mmr.commit().expect("commit changes");
+
+    let peaks = get_peaks(mmr_size)
+        .into_iter()
+        .map(|pos| (&store).get_elem(pos).unwrap().unwrap())
+        .collect();
+    let mut accumulator = PeaksAccumulator::<_, MergeNumberHash>::new(mmr_size, peaks).unwrap();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(mmr_size, &store);
+    assert_eq!(accumulator.get_root(), mmr.get_root());
+    (count..count + new_count).for_each(|i| {
+        mmr.push(NumberHash::from(i)).unwrap();
+        accumulator.push(NumberHash::from(i)).unwrap();
+    });
+    assert_eq!(accumulator.mmr_size(), mmr.mmr_size());
+    assert_eq!(accumulator.get_root(), mmr.get_root());
+    let mmr_size = mmr.mmr_size();
+    mmr.commit().expect("commit changes");
+    let peaks: Vec<_> = get_peaks(mmr_size)
+        .into_iter()
+        .map(|pos| (&store).get_elem(pos).unwrap().unwrap())
+        .collect();
+    assert_eq!(accumulator.peaks(), &peaks[..]);
+}
+
+#[test]
+fn test_accumulator_from_mmr_peaks() {
+    test_accumulator_from_peaks(11, 5);
+    test_accumulator_from_peaks(1, 1);
+    test_accumulator_from_peaks(8, 3);
+}
+
+proptest! {
+    #[test]
+    fn test_random_accumulator_from_peaks(count in 1u32..500u32, new_count in 1u32..100u32) {
+        test_accumulator_from_peaks(count, new_count);
+    }
+}
@@ -0,0 +1,164 @@
+use super::{MergeNumberHash, NumberHash};
+use crate::{
+    leaf_index_to_pos,
+    util::{MemMMR, MemStore},
+    Error, MMRStore, PruneList, MMR,
+};
+use proptest::prelude::*;
+use rand::{seq::SliceRandom, thread_rng};
+
+fn stored_count(store: &MemStore<NumberHash>, mmr_size: u64) -> usize {
+    (0..mmr_size)
+        .filter(|&pos| store.get_elem(pos).unwrap().is_some())
+        .count()
+}
+
+fn test_prune(count: u32, pruned: Vec<u32>) {
+    let mut mmr = MemMMR::<_, MergeNumberHash>::default();
+    let positions: Vec<u64> = (0u32..count)
+        .map(|i| mmr.push(NumberHash::from(i)).unwrap())
+        .collect();
+    let root = mmr.get_root().expect("get root");
+    mmr.prune(pruned.iter().map(|&i| positions[i as usize]).collect())
+        .expect("prune");
+    assert_eq!(mmr.get_root().expect("get root"), root);
+
+    for i in 0..count {
+        let pos = positions[i as usize];
+        if pruned.contains(&i) {
+            assert_eq!(
+                mmr.gen_proof(vec![pos]).map(|_| ()),
+                Err(Error::GenProofForPrunedLeaves)
+            );
+        } else {
+            let proof = mmr.gen_proof(vec![pos]).expect("gen proof");
+            assert!(proof
+                .verify(root.clone(), vec![(pos, NumberHash::from(i))])
+                .unwrap());
+        }
+    }
+
+    // the pruned MMR can still grow
+    let mut unpruned_mmr = MemMMR::<_, MergeNumberHash>::default();
+    (0u32..count).for_each(|i| {
+        unpruned_mmr.push(NumberHash::from(i)).unwrap();
+    });
+    (count..count + 10).for_each(|i| {
+        mmr.push(NumberHash::from(i)).unwrap();
+        unpruned_mmr.push(NumberHash::from(i)).unwrap();
+    });
+    assert_eq!(
+        mmr.get_root().expect("get root"),
+        unpruned_mmr.get_root().expect("get root")
+    );
+}
+
+#[test]
+fn test_prune_leaves() {
+    test_prune(11, vec![0]);
+    test_prune(11, vec![3, 4, 5]);
+    test_prune(11, vec![10]);
+    test_prune(11, (0..8).collect());
+    test_prune(11, (0..11).collect());
+}
+
+#[test]
+fn test_prune_compaction() {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    (0u32..8).for_each(|i| {
+        mmr.push(NumberHash::from(i)).unwrap();
+    });
+    let mmr_size = mmr.mmr_size();
+    // a single pruned leaf is kept as pruned root
+    mmr.prune(vec![leaf_index_to_pos(0)]).expect("prune");
+    assert_eq!(mmr.prune_list().pruned_roots(), vec![0]);
+    // sibling leaves are compacted into their parent
+    mmr.prune(vec![leaf_index_to_pos(1)]).expect("prune");
+    assert_eq!(mmr.prune_list().pruned_roots(), vec![2]);
+    mmr.prune(vec![leaf_index_to_pos(2), leaf_index_to_pos(3)])
+        .expect("prune");
+    assert_eq!(mmr.prune_list().pruned_roots(), vec![6]);
+    mmr.commit().expect("commit changes");
+    assert_eq!(stored_count(&store, mmr_size), mmr_size as usize - 6);
+    assert!((&store).get_elem(6).unwrap().is_some());
+}
+
+#[test]
+fn test_prune_internal_node() {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    (0u32..11).for_each(|i| {
+        mmr.push(NumberHash::from(i)).unwrap();
+    });
+    let root = mmr.get_root().expect("get root");
+    let mmr_size = mmr.mmr_size();
+    // prune the subtree of leaves 4..8
+    mmr.prune(vec![13]).expect("prune");
+    assert!(mmr.prune_list().is_removed(leaf_index_to_pos(5)));
+    assert!(!mmr.prune_list().is_removed(13));
+    assert_eq!(mmr.get_root().expect("get root"), root);
+    mmr.commit().expect("commit changes");
+    assert_eq!(stored_count(&store, mmr_size), mmr_size as usize - 6);
+
+    let mmr = MMR::<_, MergeNumberHash, _>::with_prune_list(
+        mmr_size,
+        &store,
+        PruneList::from_pruned_roots(vec![13]),
+    );
+    let pos = leaf_index_to_pos(2);
+    let proof = mmr.gen_proof(vec![pos]).expect("gen proof");
+    assert!(proof
+        .verify(root, vec![(pos, NumberHash::from(2))])
+        .unwrap());
+    assert_eq!(
+        mmr.gen_proof(vec![leaf_index_to_pos(5)]).map(|_| ()),
+        Err(Error::GenProofForPrunedLeaves)
+    );
+}
+
+#[test]
+fn test_prune_invalid_position() {
+    let mut mmr = MemMMR::<_, MergeNumberHash>::default();
+    (0u32..11).for_each(|i| {
+        mmr.push(NumberHash::from(i)).unwrap();
+    });
+    assert_eq!(mmr.prune(vec![19]), Err(Error::PruneInvalidPosition));
+}
+
+#[test]
+fn test_rewind_pruned_mmr() {
+    let mut mmr = MemMMR::<_, MergeNumberHash>::default();
+    (0u32..4).for_each(|i| {
+        mmr.push(NumberHash::from(i)).unwrap();
+    });
+    let root = mmr.get_root().expect("get root");
+    let mmr_size = 7;
+    (4u32..6).for_each(|i| {
+        mmr.push(NumberHash::from(i)).unwrap();
+    });
+    mmr.prune(vec![
+        leaf_index_to_pos(0),
+        leaf_index_to_pos(1),
+        leaf_index_to_pos(4),
+    ])
+    .expect("prune");
+    // can rewind pruned subtrees which are entirely removed
+    mmr.rewind(mmr_size).expect("rewind");
+    assert_eq!(mmr.get_root().expect("get root"), root);
+    assert_eq!(mmr.prune_list().pruned_roots(), vec![2]);
+    // leaf 0 and 1 are removed
+    assert_eq!(mmr.rewind(1), Err(Error::RewindPrunedNodes));
+}
+
+proptest! {
+    #[test]
+    fn test_random_prune(count in 10u32..200u32) {
+        let mut leaves: Vec<u32> = (0..count).collect();
+        let mut rng = thread_rng();
+        leaves.shuffle(&mut rng);
+        let pruned_count = rng.gen_range(1, count);
+        leaves.truncate(pruned_count as usize);
+        test_prune(count, leaves);
+    }
+}
@@ -0,0 +1,115 @@
+use super::{MergeNumberHash, NumberHash};
+use crate::{leaf_index_to_pos, util::MemStore, Error, LeftToRightBagging, PeakBagging, MMR};
+use proptest::prelude::*;
+use rand::{thread_rng, Rng};
+
+fn test_range_proof<B: PeakBagging<NumberHash>>(count: u32, start_leaf: u32, end_leaf: u32)
+where
+    B::Root: PartialEq + Clone,
+{
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _, B>::new(0, &store);
+    mmr.push_batch((0u32..count).map(NumberHash::from))
+        .expect("push");
+    let root = mmr.get_root().expect("get root");
+    let proof = mmr
+        .gen_range_proof(u64::from(start_leaf), u64::from(end_leaf))
+        .expect("gen range proof");
+    let leaves: Vec<NumberHash> = (start_leaf..end_leaf).map(NumberHash::from).collect();
+    assert!(proof
+        .verify(root.clone(), u64::from(start_leaf), &leaves)
+        .unwrap());
+    // proof items are at most two siblings per height and the peaks
+    assert!(proof.proof_items().len() <= 3 * 32);
+    // leaves must be in order and at the right place
+    let mut reversed = leaves.clone();
+    reversed.reverse();
+    if leaves.len() > 1 {
+        assert!(!proof
+            .verify(root.clone(), u64::from(start_leaf), &reversed)
+            .unwrap());
+    }
+    if end_leaf < count {
+        assert!(!proof
+            .verify(root.clone(), u64::from(start_leaf) + 1, &leaves)
+            .unwrap_or(false));
+    }
+    assert!(!proof
+        .verify(root, u64::from(start_leaf), &leaves[1..])
+        .unwrap_or(false));
+}
+
+#[test]
+fn test_all_ranges() {
+    for count in 1..=20 {
+        for start_leaf in 0..count {
+            for end_leaf in start_leaf + 1..=count {
+                test_range_proof::<crate::RightToLeftBagging>(count, start_leaf, end_leaf);
+                test_range_proof::<LeftToRightBagging>(count, start_leaf, end_leaf);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_range_proof_size() {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    mmr.push_batch((0u32..10_000).map(NumberHash::from))
+        .expect("push");
+    let proof = mmr.gen_range_proof(1_000, 9_000).expect("gen range proof");
+    // 2 boundaries of 14 heights, 5 peaks on the left and the bagged right hand side peaks
+    assert!(proof.proof_items().len() <= 2 * 14 + 6);
+    let leaves: Vec<NumberHash> = (1_000..9_000).map(NumberHash::from).collect();
+    assert!(proof
+        .verify(mmr.get_root().unwrap(), 1_000, &leaves)
+        .unwrap());
+}
+
+#[test]
+fn test_gen_range_proof_for_invalid_range() {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    mmr.push_batch((0u32..11).map(NumberHash::from))
+        .expect("push");
+    for (start_leaf, end_leaf) in [(3, 3), (5, 3), (0, 12), (11, 12)] {
+        assert_eq!(
+            mmr.gen_range_proof(start_leaf, end_leaf).map(|_| ()),
+            Err(Error::GenProofForInvalidLeaves)
+        );
+    }
+    let proof = mmr.gen_range_proof(0, 11).expect("gen range proof");
+    assert!(proof.proof_items().is_empty());
+    assert_eq!(
+        proof.calculate_root(0, &[]).map(|_| ()),
+        Err(Error::CorruptedProof)
+    );
+}
+
+#[test]
+fn test_gen_range_proof_for_pruned_leaves() {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    mmr.push_batch((0u32..11).map(NumberHash::from))
+        .expect("push");
+    let root = mmr.get_root().expect("get root");
+    // prune the subtree of leaves 0..4
+    mmr.prune(vec![leaf_index_to_pos(3) + 2]).expect("prune");
+    assert_eq!(
+        mmr.gen_range_proof(2, 6).map(|_| ()),
+        Err(Error::GenProofForPrunedLeaves)
+    );
+    let proof = mmr.gen_range_proof(4, 6).expect("gen range proof");
+    let leaves: Vec<NumberHash> = (4..6).map(NumberHash::from).collect();
+    assert!(proof.verify(root, 4, &leaves).unwrap());
+}
+
+proptest! {
+    #[test]
+    fn test_random_range_proof(count in 10u32..1000u32) {
+        let mut rng = thread_rng();
+        let start_leaf = rng.gen_range(0, count);
+        let end_leaf = rng.gen_range(start_leaf + 1, count + 1);
+        test_range_proof::<crate::RightToLeftBagging>(count, start_leaf, end_leaf);
+    }
+}
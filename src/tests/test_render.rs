@@ -0,0 +1,85 @@
+use super::{MergeNumberHash, NumberHash};
+use crate::{util::MemStore, Error, Highlight, LeftToRightBagging, MMRRender, MMR};
+
+#[test]
+fn test_render_ascii() {
+    let render = MMRRender::new(11).unwrap();
+    assert_eq!(
+        render.to_ascii(),
+        concat!(
+            "       6\n",
+            "   2       5       9\n",
+            " 0   1   3   4   7   8  10\n",
+        )
+    );
+    assert_eq!(MMRRender::new(0).unwrap().to_ascii(), "\n");
+    assert_eq!(MMRRender::new(5).map(|_| ()), Err(Error::InvalidMMRSize));
+}
+
+#[test]
+fn test_render_proof() {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    mmr.push_batch((0u32..11).map(NumberHash::from)).unwrap();
+    let proof = mmr.gen_proof(vec![3, 7]).unwrap();
+    let render = MMRRender::new(mmr.mmr_size())
+        .unwrap()
+        .with_proof(&proof, &[3, 7])
+        .unwrap();
+    assert_eq!(render.highlight(3), Some(Highlight::Proven));
+    assert_eq!(render.highlight(4), Some(Highlight::ProofItem(0)));
+    assert_eq!(render.highlight(12), Some(Highlight::ProofItem(3)));
+    assert_eq!(render.highlight(17), Some(Highlight::BaggedPeak(4)));
+    assert_eq!(render.highlight(18), Some(Highlight::BaggedPeak(4)));
+    assert_eq!(render.highlight(14), None);
+    assert_eq!(proof.proof_items().len(), 5);
+    let ascii = render.to_ascii();
+    assert!(ascii.contains("<3>   [4]   <7>   [8]"));
+    assert!(ascii.ends_with("proof: 0=[4] 1=[8] 2=[2] 3=[12] 4={17}+{18}\n"));
+    let dot = render.to_dot();
+    assert!(dot.starts_with("digraph mmr {\n"));
+    assert!(dot.contains("    n3 [label=\"3\", style=filled, fillcolor=lightblue];\n"));
+    assert!(dot.contains("    n4 [label=\"4\\nproof[0]\", style=filled, fillcolor=orange];\n"));
+    assert!(dot.contains("    n17 -> n15;\n    n17 -> n16;\n"));
+    assert!(dot.contains("    { rank=same; n6; n13; }\n"));
+
+    // a proof of another MMR
+    let render = MMRRender::new(11).unwrap();
+    assert_eq!(
+        render.with_proof(&proof, &[3, 7]).map(|_| ()),
+        Err(Error::InvalidMMRSize)
+    );
+}
+
+#[test]
+fn test_render_proof_without_bagging_rhs_peaks() {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _, LeftToRightBagging>::new(0, &store);
+    mmr.push_batch((0u32..11).map(NumberHash::from)).unwrap();
+    let proof = mmr.gen_proof(vec![0]).unwrap();
+    let render = MMRRender::new(mmr.mmr_size())
+        .unwrap()
+        .with_proof(&proof, &[0])
+        .unwrap();
+    assert_eq!(render.highlight(17), Some(Highlight::ProofItem(3)));
+    assert_eq!(render.highlight(18), Some(Highlight::ProofItem(4)));
+}
+
+#[test]
+fn test_render_labels() {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    mmr.push_batch((0u32..3).map(NumberHash::from)).unwrap();
+    mmr.commit().unwrap();
+    let render = MMRRender::new(4)
+        .unwrap()
+        .with_labels(&&store, |elem: &NumberHash| {
+            faster_hex::hex_string(&elem.0[..2]).unwrap()
+        })
+        .unwrap();
+    let label = faster_hex::hex_string(&NumberHash::from(0).0[..2]).unwrap();
+    assert!(render.to_ascii().contains(&format!("\n0: {}\n", label)));
+    assert!(render
+        .to_dot()
+        .contains(&format!("    n0 [label=\"0\\n{}\"];\n", label)));
+}
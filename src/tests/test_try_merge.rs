@@ -0,0 +1,66 @@
+use crate::{util::MemStore, Error, MerkleProof, Result, TryMerge, MMR};
+
+/// Sum of difficulty, fail on overflow
+struct MergeDifficulty;
+
+impl TryMerge for MergeDifficulty {
+    type Item = u64;
+    fn try_merge(lhs: &Self::Item, rhs: &Self::Item) -> Result<Self::Item> {
+        lhs.checked_add(*rhs)
+            .ok_or_else(|| Error::MergeError("difficulty overflow".into()))
+    }
+}
+
+fn overflow_error() -> Error {
+    Error::MergeError("difficulty overflow".into())
+}
+
+#[test]
+fn test_try_merge() {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeDifficulty, _>::new(0, &store);
+    let positions: Vec<u64> = (1u64..=11).map(|i| mmr.push(i).unwrap()).collect();
+    assert_eq!(mmr.get_root(), Ok(66));
+    let proof = mmr.gen_proof(vec![positions[4]]).expect("gen proof");
+    assert!(proof.verify(66, vec![(positions[4], 5)]).unwrap());
+}
+
+#[test]
+fn test_push_merge_error() {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeDifficulty, _>::new(0, &store);
+    mmr.push(u64::MAX).unwrap();
+    assert_eq!(mmr.push(1), Err(overflow_error()));
+}
+
+#[test]
+fn test_get_root_merge_error() {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeDifficulty, _>::new(0, &store);
+    // two peaks, merged only when bagging
+    mmr.push(u64::MAX / 2).unwrap();
+    mmr.push(u64::MAX / 2).unwrap();
+    mmr.push(u64::MAX / 2).unwrap();
+    assert_eq!(mmr.get_root(), Err(overflow_error()));
+}
+
+#[test]
+fn test_gen_proof_merge_error() {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeDifficulty, _>::new(0, &store);
+    // peaks of 4 leaves, 2 leaves and 1 leaf, rhs peaks are bagged in proof
+    let positions: Vec<u64> = [1, 1, 1, 1, u64::MAX / 2, u64::MAX / 2, u64::MAX]
+        .iter()
+        .map(|&elem| mmr.push(elem).unwrap())
+        .collect();
+    assert_eq!(
+        mmr.gen_proof(vec![positions[0]]).map(|_| ()),
+        Err(overflow_error())
+    );
+}
+
+#[test]
+fn test_calculate_root_merge_error() {
+    let proof = MerkleProof::<u64, MergeDifficulty>::new(3, vec![u64::MAX]);
+    assert_eq!(proof.calculate_root(vec![(0, 1)]), Err(overflow_error()));
+}
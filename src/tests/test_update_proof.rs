@@ -0,0 +1,103 @@
+use super::{MergeNumberHash, NumberHash};
+use crate::{
+    helper::get_peaks, util::MemStore, Error, LeftToRightBagging, MMRStore, PeakBagging,
+    PeaksAccumulator, MMR,
+};
+use proptest::prelude::*;
+use rand::{seq::SliceRandom, thread_rng};
+
+fn test_update_proof<B: PeakBagging<NumberHash>>(count: u32, proof_elems: Vec<u32>, new_count: u32)
+where
+    B::Root: PartialEq + core::fmt::Debug,
+{
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _, B>::new(0, &store);
+    let mut accumulator = PeaksAccumulator::<_, MergeNumberHash, B>::default();
+    let positions: Vec<u64> = (0u32..count + new_count)
+        .map(|i| mmr.push(NumberHash::from(i)).unwrap())
+        .collect();
+    let new_root = mmr.get_root().unwrap();
+    let new_mmr_size = mmr.mmr_size();
+    mmr.commit().expect("commit changes");
+    (0u32..count).for_each(|i| {
+        accumulator.push(NumberHash::from(i)).unwrap();
+    });
+
+    let leaves: Vec<(u64, NumberHash)> = proof_elems
+        .iter()
+        .map(|&i| (positions[i as usize], NumberHash::from(i)))
+        .collect();
+    let pos_list: Vec<u64> = leaves.iter().map(|(pos, _)| *pos).collect();
+    let mmr = MMR::<_, MergeNumberHash, _, B>::new(accumulator.mmr_size(), &store);
+    let proof = mmr.gen_proof(pos_list.clone()).unwrap();
+
+    let new_proof = proof
+        .update_with_new_leaves(
+            leaves.clone(),
+            accumulator.peaks().to_vec(),
+            (count..count + new_count).map(NumberHash::from).collect(),
+        )
+        .expect("update proof");
+    assert_eq!(new_proof.mmr_size(), new_mmr_size);
+    let mmr = MMR::<_, MergeNumberHash, _, B>::new(new_mmr_size, &store);
+    assert_eq!(
+        new_proof.proof_items(),
+        mmr.gen_proof(pos_list).unwrap().proof_items()
+    );
+    assert!(new_proof.verify(new_root, leaves).unwrap());
+}
+
+#[test]
+fn test_update_proof_with_new_leaves() {
+    test_update_proof::<crate::RightToLeftBagging>(11, vec![5], 1);
+    test_update_proof::<crate::RightToLeftBagging>(11, vec![0], 21);
+    test_update_proof::<crate::RightToLeftBagging>(11, vec![0, 10], 5);
+    test_update_proof::<crate::RightToLeftBagging>(1, vec![0], 1);
+    test_update_proof::<crate::RightToLeftBagging>(7, vec![6], 0);
+    test_update_proof::<LeftToRightBagging>(11, vec![3, 9], 9);
+}
+
+#[test]
+fn test_update_proof_with_wrong_peaks() {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    (0u32..11).for_each(|i| {
+        mmr.push(NumberHash::from(i)).unwrap();
+    });
+    let mmr_size = mmr.mmr_size();
+    let proof = mmr.gen_proof(vec![0]).unwrap();
+    mmr.commit().expect("commit changes");
+    let mut peaks: Vec<NumberHash> = get_peaks(mmr_size)
+        .into_iter()
+        .map(|pos| (&store).get_elem(pos).unwrap().unwrap())
+        .collect();
+    let leaves = vec![(0, NumberHash::from(0))];
+    assert!(proof
+        .update_with_new_leaves(leaves.clone(), peaks.clone(), vec![NumberHash::from(11)])
+        .is_ok());
+    peaks[2] = NumberHash::from(100);
+    assert_eq!(
+        proof
+            .update_with_new_leaves(leaves.clone(), peaks.clone(), vec![NumberHash::from(11)])
+            .map(|_| ()),
+        Err(Error::CorruptedProof)
+    );
+    peaks.pop();
+    assert_eq!(
+        proof
+            .update_with_new_leaves(leaves, peaks, vec![NumberHash::from(11)])
+            .map(|_| ()),
+        Err(Error::CorruptedProof)
+    );
+}
+
+proptest! {
+    #[test]
+    fn test_random_update_proof(count in 10u32..300u32, new_count in 0u32..100u32) {
+        let mut leaves: Vec<u32> = (0..count).collect();
+        let mut rng = thread_rng();
+        leaves.shuffle(&mut rng);
+        leaves.truncate(rng.gen_range(1, 10));
+        test_update_proof::<crate::RightToLeftBagging>(count, leaves, new_count);
+    }
+}
@@ -0,0 +1,102 @@
+use super::{MergeNumberHash, NumberHash};
+use crate::{util::MemStore, Error, MMRStore, MMR};
+use proptest::prelude::*;
+use rand::{thread_rng, Rng};
+
+fn build_mmr(store: &MemStore<NumberHash>, count: u32) -> u64 {
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, store);
+    mmr.push_batch((0u32..count).map(NumberHash::from))
+        .expect("push");
+    let mmr_size = mmr.mmr_size();
+    mmr.commit().expect("commit changes");
+    mmr_size
+}
+
+#[test]
+fn test_validate() {
+    let store = MemStore::default();
+    let mmr_size = build_mmr(&store, 2000);
+    let mmr = MMR::<_, MergeNumberHash, _>::new(mmr_size, &store);
+    assert_eq!(mmr.validate(), Ok(()));
+
+    // uncommitted nodes are validated too
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(mmr_size, &store);
+    mmr.push(NumberHash::from(2000)).expect("push");
+    assert_eq!(mmr.validate(), Ok(()));
+}
+
+#[test]
+fn test_validate_corrupted_store() {
+    let store = MemStore::default();
+    let mmr_size = build_mmr(&store, 11);
+    let mmr = MMR::<_, MergeNumberHash, _>::new(mmr_size, &store);
+    // a leaf can't be checked by itself, the parent of it is invalid
+    (&store).append(3, vec![NumberHash::from(100)]).unwrap();
+    assert_eq!(mmr.validate(), Err(Error::InvalidNode(5)));
+    (&store).append(3, vec![NumberHash::from(2)]).unwrap();
+    assert_eq!(mmr.validate(), Ok(()));
+    // a corrupted internal node is reported before its parent
+    (&store).append(13, vec![NumberHash::from(100)]).unwrap();
+    assert_eq!(mmr.validate(), Err(Error::InvalidNode(13)));
+    (&store).remove(vec![13, 17]).unwrap();
+    assert_eq!(mmr.validate(), Err(Error::MissingNode(13)));
+}
+
+#[test]
+fn test_validate_since() {
+    let store = MemStore::default();
+    let prev_mmr_size = build_mmr(&store, 11);
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(prev_mmr_size, &store);
+    mmr.push_batch((11u32..20).map(NumberHash::from))
+        .expect("push");
+    let mmr_size = mmr.mmr_size();
+    mmr.commit().expect("commit changes");
+    let mmr = MMR::<_, MergeNumberHash, _>::new(mmr_size, &store);
+
+    // nodes before the previous size are trusted
+    (&store).append(2, vec![NumberHash::from(100)]).unwrap();
+    assert_eq!(mmr.validate(), Err(Error::InvalidNode(2)));
+    assert_eq!(mmr.validate_since(prev_mmr_size), Ok(()));
+    // but a new node on top of a corrupted peak is checked
+    (&store).append(14, vec![NumberHash::from(100)]).unwrap();
+    assert_eq!(
+        mmr.validate_since(prev_mmr_size),
+        Err(Error::InvalidNode(30))
+    );
+    (&store).remove(vec![14]).unwrap();
+    assert_eq!(
+        mmr.validate_since(prev_mmr_size),
+        Err(Error::MissingNode(14))
+    );
+    assert_eq!(mmr.validate_since(mmr_size), Ok(()));
+    assert_eq!(mmr.validate_since(2), Err(Error::InvalidMMRSize));
+    assert_eq!(mmr.validate_since(mmr_size + 1), Err(Error::InvalidMMRSize));
+}
+
+#[test]
+fn test_validate_pruned_mmr() {
+    let store = MemStore::default();
+    let mmr_size = build_mmr(&store, 11);
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(mmr_size, &store);
+    mmr.prune(vec![0, 1, 7]).expect("prune");
+    mmr.validate().expect("validate");
+    let prune_list = mmr.prune_list().clone();
+    mmr.commit().expect("commit changes");
+    let mmr = MMR::<_, MergeNumberHash, _>::with_prune_list(mmr_size, &store, prune_list);
+    assert_eq!(mmr.validate(), Ok(()));
+    (&store).remove(vec![8]).unwrap();
+    assert_eq!(mmr.validate(), Err(Error::MissingNode(8)));
+}
+
+proptest! {
+    #[test]
+    fn test_random_validate(count in 10u32..500u32) {
+        let store = MemStore::default();
+        let mmr_size = build_mmr(&store, count);
+        let mmr = MMR::<_, MergeNumberHash, _>::new(mmr_size, &store);
+        prop_assert_eq!(mmr.validate(), Ok(()));
+        let pos = thread_rng().gen_range(0, mmr_size);
+        (&store).remove(vec![pos]).unwrap();
+        prop_assert_eq!(mmr.validate(), Err(Error::MissingNode(pos)));
+    }
+}
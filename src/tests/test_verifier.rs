@@ -0,0 +1,117 @@
+use super::{MergeNumberHash, NumberHash};
+use crate::{calculate_root_without_alloc, util::MemStore, verify_without_alloc, Error, MMR};
+use proptest::prelude::*;
+use rand::{seq::SliceRandom, thread_rng};
+
+fn test_verifier(count: u32, proof_elems: Vec<u32>) {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    let positions: Vec<u64> = (0u32..count)
+        .map(|i| mmr.push(NumberHash::from(i)).unwrap())
+        .collect();
+    let root = mmr.get_root().expect("get root");
+    let mut leaves: Vec<(u64, NumberHash)> = proof_elems
+        .iter()
+        .map(|&i| (positions[i as usize], NumberHash::from(i)))
+        .collect();
+    leaves.sort_by_key(|(pos, _)| *pos);
+    let proof = mmr
+        .gen_proof(leaves.iter().map(|(pos, _)| *pos).collect())
+        .expect("gen proof");
+    assert_eq!(
+        calculate_root_without_alloc::<_, MergeNumberHash, 64>(
+            proof.mmr_size(),
+            proof.proof_items(),
+            &leaves
+        ),
+        proof.calculate_root(leaves.clone())
+    );
+    assert!(verify_without_alloc::<_, MergeNumberHash, 64>(
+        &root,
+        proof.mmr_size(),
+        proof.proof_items(),
+        &leaves
+    )
+    .unwrap());
+    // a wrong leaf must be rejected
+    leaves[0].1 = NumberHash::from(count);
+    assert!(!verify_without_alloc::<_, MergeNumberHash, 64>(
+        &root,
+        proof.mmr_size(),
+        proof.proof_items(),
+        &leaves
+    )
+    .unwrap());
+}
+
+#[test]
+fn test_verify_without_alloc() {
+    test_verifier(11, vec![5]);
+    test_verifier(11, vec![0, 3, 10]);
+    test_verifier(1, vec![0]);
+    test_verifier(100, vec![0, 1, 2, 3, 99]);
+}
+
+#[test]
+fn test_verify_without_alloc_buffer_too_small() {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    let positions: Vec<u64> = (0u32..16)
+        .map(|i| mmr.push(NumberHash::from(i)).unwrap())
+        .collect();
+    let leaves: Vec<(u64, NumberHash)> = (0u32..4)
+        .map(|i| (positions[i as usize], NumberHash::from(i)))
+        .collect();
+    let proof = mmr
+        .gen_proof(leaves.iter().map(|(pos, _)| *pos).collect())
+        .expect("gen proof");
+    assert_eq!(
+        calculate_root_without_alloc::<_, MergeNumberHash, 2>(
+            proof.mmr_size(),
+            proof.proof_items(),
+            &leaves
+        ),
+        Err(Error::BufferTooSmall)
+    );
+    assert_eq!(
+        calculate_root_without_alloc::<_, MergeNumberHash, 4>(
+            proof.mmr_size(),
+            proof.proof_items(),
+            &leaves
+        ),
+        mmr.get_root()
+    );
+}
+
+#[test]
+fn test_verify_without_alloc_unsorted_leaves() {
+    let store = MemStore::default();
+    let mut mmr = MMR::<_, MergeNumberHash, _>::new(0, &store);
+    let positions: Vec<u64> = (0u32..11)
+        .map(|i| mmr.push(NumberHash::from(i)).unwrap())
+        .collect();
+    let leaves = vec![
+        (positions[3], NumberHash::from(3)),
+        (positions[1], NumberHash::from(1)),
+    ];
+    let proof = mmr.gen_proof(vec![positions[1], positions[3]]).unwrap();
+    assert_eq!(
+        calculate_root_without_alloc::<_, MergeNumberHash, 64>(
+            proof.mmr_size(),
+            proof.proof_items(),
+            &leaves
+        ),
+        Err(Error::CorruptedProof)
+    );
+}
+
+proptest! {
+    #[test]
+    fn test_random_verify_without_alloc(count in 10u32..500u32) {
+        let mut leaves: Vec<u32> = (0..count).collect();
+        let mut rng = thread_rng();
+        leaves.shuffle(&mut rng);
+        leaves.truncate(rng.gen_range(1, 32));
+        test_verifier(count, leaves);
+    }
+}
@@ -1,5 +1,5 @@
 use crate::collections::BTreeMap;
-use crate::{vec::Vec, MMRStore, Merge, MerkleProof, Result, MMR};
+use crate::{vec::Vec, KVStore, MMRStore, MerkleProof, NodeMerge, PruneList, Result, MMR};
 use core::cell::RefCell;
 use core::fmt::Debug;
 use core::marker::PhantomData;
@@ -31,25 +31,87 @@ impl<T: Clone> MMRStore<T> for &MemStore<T> {
         }
         Ok(())
     }
+
+    fn truncate(&mut self, mmr_size: u64) -> Result<()> {
+        self.0.borrow_mut().split_off(&mmr_size);
+        Ok(())
+    }
+
+    fn remove(&mut self, pos_list: Vec<u64>) -> Result<()> {
+        let mut store = self.0.borrow_mut();
+        for pos in pos_list {
+            store.remove(&pos);
+        }
+        Ok(())
+    }
+}
+
+// the store isn't `Sync`, so the work is done before the futures, which only hold results
+#[cfg(feature = "async")]
+impl<T: Clone + Send> crate::AsyncMMRStore<T> for &MemStore<T> {
+    fn get_elem(&self, pos: u64) -> impl core::future::Future<Output = Result<Option<T>>> + Send {
+        core::future::ready(MMRStore::get_elem(self, pos))
+    }
+
+    fn append(
+        &mut self,
+        pos: u64,
+        elems: Vec<T>,
+    ) -> impl core::future::Future<Output = Result<()>> + Send {
+        core::future::ready(MMRStore::append(self, pos, elems))
+    }
+}
+
+/// Key-value store in memory, the reference implementation of `KVStore`
+#[derive(Clone, Default)]
+pub struct MemKVStore(RefCell<BTreeMap<Vec<u8>, Vec<u8>>>);
+
+impl MemKVStore {
+    pub fn len(&self) -> usize {
+        self.0.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.borrow().is_empty()
+    }
+}
+
+impl KVStore for &MemKVStore {
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        Ok(self.0.borrow().get(key).cloned())
+    }
+
+    fn write_batch(&mut self, batch: Vec<(Vec<u8>, Option<Vec<u8>>)>) -> Result<()> {
+        let mut store = self.0.borrow_mut();
+        for (key, value) in batch {
+            match value {
+                Some(value) => store.insert(key, value),
+                None => store.remove(&key),
+            };
+        }
+        Ok(())
+    }
 }
 
 pub struct MemMMR<T, M> {
     store: MemStore<T>,
     mmr_size: u64,
+    prune_list: PruneList,
     merge: PhantomData<M>,
 }
 
-impl<T: Clone + Debug + PartialEq, M: Merge<Item = T>> Default for MemMMR<T, M> {
+impl<T: Clone + Debug + PartialEq, M: NodeMerge<Item = T>> Default for MemMMR<T, M> {
     fn default() -> Self {
         Self::new(0, Default::default())
     }
 }
 
-impl<T: Clone + Debug + PartialEq, M: Merge<Item = T>> MemMMR<T, M> {
+impl<T: Clone + Debug + PartialEq, M: NodeMerge<Item = T>> MemMMR<T, M> {
     pub fn new(mmr_size: u64, store: MemStore<T>) -> Self {
         MemMMR {
             mmr_size,
             store,
+            prune_list: PruneList::new(),
             merge: PhantomData,
         }
     }
@@ -58,6 +120,10 @@ impl<T: Clone + Debug + PartialEq, M: Merge<Item = T>> MemMMR<T, M> {
         &self.store
     }
 
+    pub fn prune_list(&self) -> &PruneList {
+        &self.prune_list
+    }
+
     pub fn get_root(&self) -> Result<T> {
         let mmr = MMR::<T, M, &MemStore<T>>::new(self.mmr_size, &self.store);
         mmr.get_root()
@@ -71,8 +137,43 @@ impl<T: Clone + Debug + PartialEq, M: Merge<Item = T>> MemMMR<T, M> {
         Ok(pos)
     }
 
+    pub fn push_batch<I: IntoIterator<Item = T>>(&mut self, elems: I) -> Result<Vec<u64>> {
+        let mut mmr = MMR::<T, M, &MemStore<T>>::new(self.mmr_size, &self.store);
+        let positions = mmr.push_batch(elems)?;
+        self.mmr_size = mmr.mmr_size();
+        mmr.commit()?;
+        Ok(positions)
+    }
+
+    pub fn rewind(&mut self, mmr_size: u64) -> Result<()> {
+        let mut mmr = MMR::<T, M, &MemStore<T>>::with_prune_list(
+            self.mmr_size,
+            &self.store,
+            self.prune_list.clone(),
+        );
+        mmr.rewind(mmr_size)?;
+        self.mmr_size = mmr.mmr_size();
+        self.prune_list = mmr.prune_list().clone();
+        mmr.commit()
+    }
+
+    pub fn prune(&mut self, pos_list: Vec<u64>) -> Result<()> {
+        let mut mmr = MMR::<T, M, &MemStore<T>>::with_prune_list(
+            self.mmr_size,
+            &self.store,
+            self.prune_list.clone(),
+        );
+        mmr.prune(pos_list)?;
+        self.prune_list = mmr.prune_list().clone();
+        mmr.commit()
+    }
+
     pub fn gen_proof(&self, pos_list: Vec<u64>) -> Result<MerkleProof<T, M>> {
-        let mmr = MMR::<T, M, &MemStore<T>>::new(self.mmr_size, &self.store);
+        let mmr = MMR::<T, M, &MemStore<T>>::with_prune_list(
+            self.mmr_size,
+            &self.store,
+            self.prune_list.clone(),
+        );
         mmr.gen_proof(pos_list)
     }
 }
@@ -0,0 +1,211 @@
+//! Allocation-free verifier
+//!
+//! Verify merkle proofs on `no_std` targets without an allocator. Leaves are borrowed from
+//! the caller and intermediate nodes are kept in fixed-capacity buffers on the stack.
+//! Peaks are bagged from right to left, the same as the default `RightToLeftBagging`.
+
+use crate::helper::{
+    is_valid_mmr_size, parent_offset, peaks_iter, pos_height_in_tree, sibling_offset,
+};
+use crate::{Error, NodeMerge, Result};
+
+/// Fixed-capacity buffer of `N` items
+struct ArrayBuf<T, const N: usize> {
+    items: [Option<T>; N],
+    len: usize,
+}
+
+impl<T, const N: usize> ArrayBuf<T, N> {
+    fn new() -> Self {
+        ArrayBuf {
+            items: [(); N].map(|_| None),
+            len: 0,
+        }
+    }
+
+    fn insert(&mut self, index: usize, item: T) -> Result<()> {
+        if self.len == N {
+            return Err(Error::BufferTooSmall);
+        }
+        self.items[index..=self.len].rotate_right(1);
+        self.items[index] = Some(item);
+        self.len += 1;
+        Ok(())
+    }
+
+    fn push(&mut self, item: T) -> Result<()> {
+        self.insert(self.len, item)
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        self.items[self.len].take()
+    }
+
+    fn last(&self) -> Option<&T> {
+        self.len.checked_sub(1).and_then(|i| self.items[i].as_ref())
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &T> {
+        self.items[..self.len].iter().filter_map(Option::as_ref)
+    }
+}
+
+/// insert into a queue ordered by (height, pos) from back to front,
+/// so the front of queue is popped from the back of buffer
+fn push_by_height<T, const N: usize>(
+    queue: &mut ArrayBuf<(u64, T, u32), N>,
+    elem: (u64, T, u32),
+) -> Result<()> {
+    let elem_key = (elem.2, elem.0);
+    let index = queue
+        .iter()
+        .position(|(pos, _, height)| (*height, *pos) < elem_key)
+        .unwrap_or(queue.len);
+    queue.insert(index, elem)
+}
+
+// hash a leaf by `M::hash_leaf`, nodes above leaves are taken as they are
+fn leaf_node<T: Clone, M: NodeMerge<Item = T>>(pos: u64, item: &T) -> Result<T> {
+    if pos_height_in_tree(pos) == 0 {
+        M::hash_leaf(item.clone())
+    } else {
+        Ok(item.clone())
+    }
+}
+
+fn calculate_peak_root<'a, T: 'a + Clone, M: NodeMerge<Item = T>, const N: usize>(
+    leaves: &[(u64, T)],
+    peak_pos: u64,
+    proof_iter: &mut impl Iterator<Item = &'a T>,
+) -> Result<T> {
+    debug_assert!(!leaves.is_empty(), "can't be empty");
+    // (position, hash, height)
+    let mut queue: ArrayBuf<(u64, T, u32), N> = ArrayBuf::new();
+    for (pos, item) in leaves {
+        let node = leaf_node::<_, M>(*pos, item)?;
+        push_by_height(&mut queue, (*pos, node, pos_height_in_tree(*pos)))?;
+    }
+
+    // calculate tree root from each items
+    while let Some((pos, item, height)) = queue.pop() {
+        if pos == peak_pos {
+            // return root
+            return Ok(item);
+        }
+        // calculate sibling
+        let next_height = pos_height_in_tree(pos + 1);
+        let (sib_pos, parent_pos) = {
+            let sibling_offset = sibling_offset(height);
+            if next_height > height {
+                // implies pos is right sibling
+                (pos - sibling_offset, pos + 1)
+            } else {
+                // pos is left sibling
+                (pos + sibling_offset, pos + parent_offset(height))
+            }
+        };
+        let sibling_item = if Some(&sib_pos) == queue.last().map(|(pos, _, _)| pos) {
+            queue.pop().map(|(_, item, _)| item).unwrap()
+        } else {
+            proof_iter.next().ok_or(Error::CorruptedProof)?.clone()
+        };
+
+        let parent_item = if next_height > height {
+            M::merge_node(parent_pos, height + 1, &sibling_item, &item)?
+        } else {
+            M::merge_node(parent_pos, height + 1, &item, &sibling_item)?
+        };
+
+        if parent_pos < peak_pos {
+            push_by_height(&mut queue, (parent_pos, parent_item, height + 1))?;
+        } else {
+            return Ok(parent_item);
+        }
+    }
+    Err(Error::CorruptedProof)
+}
+
+/// Calculate root from proof items and leaves given by `(pos, leaf)` without allocation.
+///
+/// Leaves must be sorted by position, they are hashed by `M::hash_leaf` as in `MMR::push`.
+/// `N` is the capacity of buffers, it must be no less than the count of peaks and
+/// the count of nodes under a peak, otherwise `Error::BufferTooSmall` is returned.
+pub fn calculate_root_without_alloc<T: Clone, M: NodeMerge<Item = T>, const N: usize>(
+    mmr_size: u64,
+    proof: &[T],
+    leaves: &[(u64, T)],
+) -> Result<T> {
+    if !is_valid_mmr_size(mmr_size) {
+        return Err(Error::InvalidMMRSize);
+    }
+    if leaves.is_empty() || leaves.windows(2).any(|w| w[0].0 >= w[1].0) {
+        return Err(Error::CorruptedProof);
+    }
+    // special handle the only 1 leaf MMR
+    if mmr_size == 1 && leaves.len() == 1 && leaves[0].0 == 0 {
+        return leaf_node::<_, M>(0, &leaves[0].1);
+    }
+    let mut proof_iter = proof.iter();
+    let mut leaves = leaves;
+    let mut peaks_hashes: ArrayBuf<T, N> = ArrayBuf::new();
+    for peak_pos in peaks_iter(mmr_size) {
+        let count = leaves
+            .iter()
+            .position(|(pos, _)| *pos > peak_pos)
+            .unwrap_or(leaves.len());
+        let (peak_leaves, rest) = leaves.split_at(count);
+        leaves = rest;
+        let peak_root = if peak_leaves.len() == 1 && peak_leaves[0].0 == peak_pos {
+            // leaf is the peak
+            leaf_node::<_, M>(peak_pos, &peak_leaves[0].1)?
+        } else if peak_leaves.is_empty() {
+            // if empty, means the next proof is a peak root or rhs bagged root
+            if let Some(peak_root) = proof_iter.next() {
+                peak_root.clone()
+            } else {
+                // means that either all right peaks are bagged, or proof is corrupted
+                // so we break loop and check no items left
+                break;
+            }
+        } else {
+            calculate_peak_root::<_, M, N>(peak_leaves, peak_pos, &mut proof_iter)?
+        };
+        peaks_hashes.push(peak_root)?;
+    }
+
+    // ensure nothing left in leaves
+    if !leaves.is_empty() {
+        return Err(Error::CorruptedProof);
+    }
+
+    // check rhs peaks
+    if let Some(rhs_peaks_hashes) = proof_iter.next() {
+        peaks_hashes.push(rhs_peaks_hashes.clone())?;
+    }
+    // ensure nothing left in proof_iter
+    if proof_iter.next().is_some() {
+        return Err(Error::CorruptedProof);
+    }
+
+    // bagging from right to left via merge(right, left)
+    let mut root = peaks_hashes.pop().ok_or(Error::CorruptedProof)?;
+    while let Some(left_peak) = peaks_hashes.pop() {
+        root = M::merge_peaks(&root, &left_peak)?;
+    }
+    Ok(root)
+}
+
+/// Verify proof items against `root` without allocation, see `calculate_root_without_alloc`
+pub fn verify_without_alloc<T: Clone + PartialEq, M: NodeMerge<Item = T>, const N: usize>(
+    root: &T,
+    mmr_size: u64,
+    proof: &[T],
+    leaves: &[(u64, T)],
+) -> Result<bool> {
+    calculate_root_without_alloc::<T, M, N>(mmr_size, proof, leaves)
+        .map(|calculated_root| &calculated_root == root)
+}
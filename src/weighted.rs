@@ -0,0 +1,106 @@
+//! Weighted MMR
+//!
+//! Elems are annotated with weights, such as block hashes with difficulties, and a node
+//! carries the sum of weights of its children. Leaves are ordered by cumulative weight,
+//! so a leaf can be found by a weight in its interval, which is the base of FlyClient.
+//!
+//! A proof of a leaf is a merkle proof of it, weights of the left siblings and the left
+//! peaks in the proof add up to the cumulative weight on the left of the leaf.
+//! The merge must commit to the weights of children, otherwise a prover can lie about them.
+//!
+//! references:
+//! https://eprint.iacr.org/2019/226.pdf
+
+use crate::helper::{
+    get_peaks, leaf_index_to_pos, mmr_size_to_leaf_count, parent_offset, pos_height_in_tree,
+};
+use crate::vec;
+use crate::{Error, MerkleProof, NodeMerge, PeakBagging, Result, RightToLeftBagging};
+use core::fmt::Debug;
+use core::ops::Add;
+
+/// Elem annotated with a weight, the merge must sum weights of children into the parent
+pub trait Weighted {
+    type Weight: Copy + Ord + Default + Add<Output = Self::Weight>;
+    fn weight(&self) -> Self::Weight;
+}
+
+/// Proof of a leaf and the cumulative weight on the left of it
+#[derive(Debug)]
+pub struct WeightProof<T, M, B = RightToLeftBagging> {
+    leaf_index: u64,
+    proof: MerkleProof<T, M, B>,
+}
+
+impl<T: PartialEq + Debug + Clone + Weighted, M: NodeMerge<Item = T>, B: PeakBagging<T>>
+    WeightProof<T, M, B>
+{
+    pub fn new(leaf_index: u64, proof: MerkleProof<T, M, B>) -> Self {
+        WeightProof { leaf_index, proof }
+    }
+
+    pub fn leaf_index(&self) -> u64 {
+        self.leaf_index
+    }
+
+    pub fn proof(&self) -> &MerkleProof<T, M, B> {
+        &self.proof
+    }
+
+    /// cumulative weight on the left of the leaf, calculated from proof items
+    /// 1. peaks on the left hand side come first in proof
+    /// 2. then siblings from the leaf to its peak, add the left ones
+    pub fn left_weight(&self) -> Result<T::Weight> {
+        let mmr_size = self.proof.mmr_size();
+        if self.leaf_index >= mmr_size_to_leaf_count(mmr_size)? {
+            return Err(Error::CorruptedProof);
+        }
+        let pos = leaf_index_to_pos(self.leaf_index);
+        let peaks = get_peaks(mmr_size);
+        let lhs_peaks_count = peaks.iter().take_while(|&&peak_pos| peak_pos < pos).count();
+        let peak_height = pos_height_in_tree(peaks[lhs_peaks_count]);
+        let mut proof_iter = self.proof.proof_items().iter();
+        let mut left_weight = T::Weight::default();
+        for _ in 0..lhs_peaks_count {
+            left_weight = left_weight + proof_iter.next().ok_or(Error::CorruptedProof)?.weight();
+        }
+        let mut pos = pos;
+        for height in 0..peak_height {
+            let sibling = proof_iter.next().ok_or(Error::CorruptedProof)?;
+            if pos_height_in_tree(pos + 1) > height {
+                // pos is the right sibling
+                left_weight = left_weight + sibling.weight();
+                pos += 1;
+            } else {
+                pos += parent_offset(height);
+            }
+        }
+        Ok(left_weight)
+    }
+
+    /// verify `leaf` is at `leaf_index` and `left_weight` is the cumulative weight on the left of it
+    pub fn verify_left_weight(&self, root: B::Root, leaf: T, left_weight: T::Weight) -> Result<bool>
+    where
+        B::Root: PartialEq,
+    {
+        Ok(self.left_weight()? == left_weight && self.verify_leaf(root, leaf)?)
+    }
+
+    /// verify `leaf` is at `leaf_index` and `weight` is in the cumulative weight interval of it
+    pub fn verify_weight(&self, root: B::Root, leaf: T, weight: T::Weight) -> Result<bool>
+    where
+        B::Root: PartialEq,
+    {
+        let left_weight = self.left_weight()?;
+        let in_interval = left_weight <= weight && weight < left_weight + leaf.weight();
+        Ok(in_interval && self.verify_leaf(root, leaf)?)
+    }
+
+    fn verify_leaf(&self, root: B::Root, leaf: T) -> Result<bool>
+    where
+        B::Root: PartialEq,
+    {
+        self.proof
+            .verify_by_leaf_indices(root, vec![(self.leaf_index, leaf)])
+    }
+}